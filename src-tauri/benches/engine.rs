@@ -0,0 +1,144 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use quantum_gomoku::{
+    ai::{Agent, GreedyAgent, RandomAgent},
+    analysis::observation_outcome,
+    game::{Game, BOARD_SIZE},
+    zobrist,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+const CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+
+fn position(stones: usize, seed: u64) -> Game {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new();
+    let mut positions = game.legal_placements();
+    positions.shuffle(&mut rng);
+
+    for (x, y) in positions.into_iter().take(stones) {
+        game.place_stone_prohibitly(x, y).unwrap();
+    }
+
+    game
+}
+
+fn positions() -> [(&'static str, Game); 3] {
+    [
+        ("empty", position(0, 1)),
+        ("mid_game", position(CELLS / 3, 1)),
+        ("full", position(CELLS - 1, 1)),
+    ]
+}
+
+fn placement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("placement");
+
+    for (name, game) in positions() {
+        let (x, y) = game.legal_placements()[0];
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(
+                || game.clone(),
+                |mut game| game.place_stone_prohibitly(black_box(x), black_box(y)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn observe(c: &mut Criterion) {
+    let mut group = c.benchmark_group("observe");
+
+    for (name, game) in positions() {
+        let mut rng = StdRng::seed_from_u64(2);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(
+                || game.clone(),
+                |mut game| game.observe_with(&mut rng),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn observation_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("observation_throughput");
+
+    for (name, game) in positions() {
+        let mut rng = StdRng::seed_from_u64(3);
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(BenchmarkId::new("sample_and_judge", name), |b| {
+            b.iter(|| game.sample_bits(&mut rng).winners())
+        });
+    }
+
+    let game = position(CELLS / 3, 1);
+    let mut rng = StdRng::seed_from_u64(4);
+    group.throughput(Throughput::Elements(1000));
+    group.bench_function("win_probability_1000_samples", |b| {
+        b.iter(|| observation_outcome(&game, 1000, &mut rng))
+    });
+
+    group.finish();
+}
+
+fn get_winners(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_winners");
+
+    for (name, game) in positions() {
+        let mut game = game;
+        game.observe_with(&mut StdRng::seed_from_u64(5));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| game.get_winners())
+        });
+    }
+
+    group.finish();
+}
+
+fn hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hashing");
+
+    for (name, game) in positions() {
+        group.bench_function(BenchmarkId::new("full_recompute", name), |b| {
+            b.iter(|| zobrist::hash(black_box(&game)))
+        });
+    }
+
+    group.finish();
+}
+
+fn agents(c: &mut Criterion) {
+    let mut group = c.benchmark_group("agents");
+    group.sample_size(20);
+
+    for (name, game) in positions() {
+        let mut random = RandomAgent::new(6);
+        group.bench_function(BenchmarkId::new("random", name), |b| {
+            b.iter(|| random.select_action(&game))
+        });
+
+        let mut greedy = GreedyAgent::new(7);
+        group.bench_function(BenchmarkId::new("greedy", name), |b| {
+            b.iter(|| greedy.select_action(&game))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    placement,
+    observe,
+    observation_throughput,
+    get_winners,
+    hashing,
+    agents
+);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use quantum_gomoku::{
+    bitboard::ObservedBits,
+    game::{scan_winners, ObservedBoard, BOARD_SIZE},
+    lines::LineTracker,
+    player::PlayerKind,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn random_board(density: f64, seed: u64) -> ObservedBoard {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+    for row in board.iter_mut() {
+        for cell in row.iter_mut() {
+            if rng.gen_bool(density) {
+                *cell = Some(if rng.gen() {
+                    PlayerKind::Black
+                } else {
+                    PlayerKind::White
+                });
+            }
+        }
+    }
+    board
+}
+
+fn win_detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("win_detection");
+
+    for (name, density) in [("empty", 0.0), ("mid_game", 0.3), ("full", 1.0)] {
+        let board = random_board(density, 42);
+        let bits = ObservedBits::from(&board);
+
+        group.bench_with_input(BenchmarkId::new("scan", name), &board, |b, board| {
+            b.iter(|| scan_winners(black_box(board)))
+        });
+        group.bench_with_input(BenchmarkId::new("bitboard", name), &bits, |b, bits| {
+            b.iter(|| black_box(bits).winners())
+        });
+    }
+
+    group.finish();
+}
+
+fn incremental_win_detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("incremental_win_detection");
+
+    for (name, density) in [("mid_game", 0.3), ("full", 1.0)] {
+        let bits = ObservedBits::from(&random_board(density, 42));
+        let mut lines = LineTracker::new();
+        lines.update(&bits);
+        let (x, y) = bits.stones.iter().next().unwrap();
+
+        group.bench_function(BenchmarkId::new("flip_one_cell", name), |b| {
+            b.iter(|| {
+                let flipped = lines.board().get(x, y).map(|kind| kind.opponent());
+                lines.set(x, y, flipped);
+                black_box(lines.winners())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, win_detection, incremental_win_detection);
+criterion_main!(benches);
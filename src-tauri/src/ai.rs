@@ -0,0 +1,376 @@
+use std::fmt::Display;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    analysis::{observation_outcome, region_observation_outcome},
+    game::{Action, Correlation, Entanglement, Game, Move, Region, Rules, BOARD_SIZE},
+    grid::Grid,
+    player::PlayerKind,
+    stone_model::StoneModelKind,
+    zobrist::{self, TranspositionTable},
+};
+
+pub trait Agent {
+    fn name(&self) -> String;
+
+    fn new_game(&mut self) -> Result<(), AgentError> {
+        Ok(())
+    }
+
+    // Starts the agent's randomness over from `seed`, as if it had just been
+    // created with it. Engines keep their own.
+    fn reseed(&mut self, _seed: u64) {}
+
+    fn select_action(&mut self, game: &Game) -> Result<Action, AgentError>;
+}
+
+impl<A: Agent + ?Sized> Agent for Box<A> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn new_game(&mut self) -> Result<(), AgentError> {
+        (**self).new_game()
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        (**self).reseed(seed)
+    }
+
+    fn select_action(&mut self, game: &Game) -> Result<Action, AgentError> {
+        (**self).select_action(game)
+    }
+}
+
+pub struct RandomAgent {
+    rng: StdRng,
+    observe_rate: f64,
+    entangle_rate: f64,
+    superpose_rate: f64,
+    flip_rate: f64,
+    influence_rate: f64,
+    certain_rate: f64,
+    erase_rate: f64,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> RandomAgent {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+            observe_rate: 0.1,
+            entangle_rate: 0.1,
+            superpose_rate: 0.1,
+            flip_rate: 0.1,
+            influence_rate: 0.1,
+            certain_rate: 0.1,
+            erase_rate: 0.1,
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn select_action(&mut self, game: &Game) -> Result<Action, AgentError> {
+        if game.winner().is_some() {
+            return Err(AgentError::GameIsOver);
+        }
+
+        let positions = game.legal_placements();
+        let can_observe = game.can_observe() && !game.history().is_empty();
+
+        if can_observe && (positions.is_empty() || self.rng.gen_bool(self.observe_rate)) {
+            if !game.rules().regional_observation {
+                return Ok(Action::Observe);
+            }
+
+            let (x, y) = (
+                self.rng.gen_range(0..BOARD_SIZE),
+                self.rng.gen_range(0..BOARD_SIZE),
+            );
+            let region = *regions_through(x, y, game.rules().grid)
+                .choose(&mut self.rng)
+                .unwrap();
+            return Ok(Action::ObserveRegion(region));
+        }
+
+        if game.rules().allow_entanglement
+            && positions.len() >= 2
+            && self.rng.gen_bool(self.entangle_rate)
+        {
+            let pair: Vec<&(usize, usize)> = positions.choose_multiple(&mut self.rng, 2).collect();
+            let correlation = if self.rng.gen() {
+                Correlation::Correlated
+            } else {
+                Correlation::Anticorrelated
+            };
+            return Ok(Action::PlaceEntangled(Entanglement {
+                first: *pair[0],
+                second: *pair[1],
+                correlation,
+            }));
+        }
+
+        if game.rules().stone_model == StoneModelKind::Amplitude
+            && self.rng.gen_bool(self.flip_rate)
+        {
+            let targets: Vec<(usize, usize)> = game
+                .stones()
+                .occupied()
+                .iter()
+                .filter(|&(x, y)| game.can_flip(x, y))
+                .collect();
+            if let Some(&(x, y)) = targets.choose(&mut self.rng) {
+                return Ok(Action::Flip(x, y));
+            }
+        }
+
+        if game.rules().erasure.is_some() && self.rng.gen_bool(self.erase_rate) {
+            let targets: Vec<(usize, usize)> = game
+                .stones()
+                .occupied()
+                .iter()
+                .filter(|&(x, y)| game.can_erase(x, y))
+                .collect();
+            if let Some(&(x, y)) = targets.choose(&mut self.rng) {
+                return Ok(Action::Erase(x, y));
+            }
+        }
+
+        if game.rules().allow_superposition && self.rng.gen_bool(self.superpose_rate) {
+            let candidates: Vec<(usize, usize)> = (!game.stones().occupied()).iter().collect();
+            let pair: Vec<&(usize, usize)> = candidates.choose_multiple(&mut self.rng, 2).collect();
+            if pair.len() == 2 && game.can_superpose([*pair[0], *pair[1]]) {
+                return Ok(Action::PlaceSuperposed([*pair[0], *pair[1]]));
+            }
+        }
+
+        let certain =
+            game.player(game.turn()).can_place_certain() && self.rng.gen_bool(self.certain_rate);
+        let influence = game.rules().influence.is_some() && self.rng.gen_bool(self.influence_rate);
+        positions
+            .choose(&mut self.rng)
+            .map(|&(x, y)| {
+                if certain {
+                    Action::PlaceCertain(x, y)
+                } else if influence {
+                    Action::PlaceInfluencer(x, y)
+                } else {
+                    Action::Place(x, y)
+                }
+            })
+            .ok_or(AgentError::NoLegalAction)
+    }
+}
+
+pub struct GreedyAgent {
+    rng: StdRng,
+    samples: usize,
+    observe_threshold: f64,
+    // Chances of winning by observing, keyed by position and region. Openings
+    // repeat from game to game, so these are kept until the rules change.
+    chances: TranspositionTable<f64>,
+    rules: Option<Rules>,
+}
+
+impl GreedyAgent {
+    pub fn new(seed: u64) -> GreedyAgent {
+        GreedyAgent {
+            rng: StdRng::seed_from_u64(seed),
+            samples: 64,
+            observe_threshold: 0.5,
+            chances: TranspositionTable::new(1 << 16),
+            rules: None,
+        }
+    }
+
+    fn score(game: &Game, x: usize, y: usize) -> f64 {
+        let me = game.turn();
+        let p_new = black_share(game.get_turn_p() as f64 / 100.0, me);
+
+        let mut score = 0.0;
+        let geometry = game.rules().geometry();
+        for &direction in geometry.directions() {
+            for offset in 0..5 {
+                let mut own = p_new;
+                let mut opponent = 0.0;
+                let mut inside = true;
+
+                for i in 0..5 {
+                    let Some((cx, cy)) = geometry.step(x, y, direction, i - offset) else {
+                        inside = false;
+                        break;
+                    };
+                    if let Some(p) = game.black_probability(cx, cy) {
+                        let share = black_share(p, me);
+                        own += share;
+                        opponent += 1.0 - share;
+                    }
+                }
+
+                if inside {
+                    score += own.powi(4) + 0.8 * opponent.powi(4);
+                }
+            }
+        }
+
+        score
+    }
+
+    // Under regional observation only the regions through the last few
+    // stones are worth looking at; elsewhere nothing has changed.
+    fn best_observation(&mut self, game: &Game) -> (Action, f64) {
+        if !game.rules().regional_observation {
+            return (Action::Observe, self.chance(game, None));
+        }
+
+        let mut regions: Vec<Region> = game
+            .history()
+            .iter()
+            .rev()
+            .filter_map(placed_cell)
+            .take(3)
+            .flat_map(|(x, y)| regions_through(x, y, game.rules().grid))
+            .collect();
+        regions.dedup();
+        if regions.is_empty() {
+            regions.push(Region::Window(BOARD_SIZE / 2, BOARD_SIZE / 2));
+        }
+
+        regions
+            .into_iter()
+            .map(|region| {
+                (
+                    Action::ObserveRegion(region),
+                    self.chance(game, Some(region)),
+                )
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("at least one region")
+    }
+
+    fn chance(&mut self, game: &Game, region: Option<Region>) -> f64 {
+        let key = game.hash() ^ region.map_or(0, zobrist::region_key);
+        if let Some(entry) = self.chances.get(key) {
+            return entry.value;
+        }
+
+        let outcome = match region {
+            Some(region) => region_observation_outcome(game, region, self.samples, &mut self.rng),
+            None => observation_outcome(game, self.samples, &mut self.rng),
+        };
+        let chance = outcome.of(game.turn());
+        self.chances.insert(key, 0, chance);
+        chance
+    }
+}
+
+fn placed_cell(mv: &Move) -> Option<(usize, usize)> {
+    match mv {
+        Move::Place { x, y, .. } => Some((*x, *y)),
+        Move::PlaceEntangled { entanglement, .. } => Some(entanglement.first),
+        Move::PlaceSuperposed(superposition) => Some(superposition.cells[0]),
+        Move::PlaceInfluencer { x, y, .. } => Some((*x, *y)),
+        _ => None,
+    }
+}
+
+fn regions_through(x: usize, y: usize, grid: Grid) -> Vec<Region> {
+    [
+        Region::Window(x, y),
+        Region::Row(y),
+        Region::Column(x),
+        Region::Diagonal(x, y),
+        Region::AntiDiagonal(x, y),
+    ]
+    .into_iter()
+    .filter(|region| region.is_valid(grid))
+    .collect()
+}
+
+fn black_share(p: f64, player: PlayerKind) -> f64 {
+    match player {
+        PlayerKind::Black => p,
+        PlayerKind::White => 1.0 - p,
+    }
+}
+
+impl Agent for GreedyAgent {
+    fn name(&self) -> String {
+        "greedy".to_string()
+    }
+
+    // Cached chances would let earlier games steer this one.
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.chances.clear();
+    }
+
+    fn select_action(&mut self, game: &Game) -> Result<Action, AgentError> {
+        if game.winner().is_some() {
+            return Err(AgentError::GameIsOver);
+        }
+
+        if self.rules != Some(*game.rules()) {
+            self.chances.clear();
+            self.rules = Some(*game.rules());
+        }
+
+        let can_observe = game.can_observe() && !game.history().is_empty();
+        let observation = if can_observe {
+            Some(self.best_observation(game))
+        } else {
+            None
+        };
+
+        if let Some((action, chance)) = observation {
+            if chance >= self.observe_threshold {
+                return Ok(action);
+            }
+        }
+
+        let mut best = Vec::new();
+        let mut best_score = f64::MIN;
+        for (x, y) in game.legal_placements() {
+            let score = Self::score(game, x, y);
+            if score > best_score + f64::EPSILON {
+                best_score = score;
+                best.clear();
+            }
+            if score >= best_score - f64::EPSILON {
+                best.push((x, y));
+            }
+        }
+
+        match best.choose(&mut self.rng) {
+            Some(&(x, y)) => Ok(Action::Place(x, y)),
+            None => observation
+                .map(|(action, _)| action)
+                .ok_or(AgentError::NoLegalAction),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum AgentError {
+    GameIsOver,
+    NoLegalAction,
+    Engine(String),
+}
+
+impl Display for AgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentError::GameIsOver => write!(f, "Game is already over"),
+            AgentError::NoLegalAction => write!(f, "No legal action"),
+            AgentError::Engine(message) => write!(f, "Engine error: {}", message),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use rand::Rng;
+
+use crate::{
+    bitboard::ObservedBits,
+    cube::{self, CubeGame},
+    game::{decide_winner, Game, Region},
+    player::PlayerKind,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct WinProbability {
+    pub black: f64,
+    pub white: f64,
+    pub none: f64,
+}
+
+impl WinProbability {
+    pub fn of(&self, player: PlayerKind) -> f64 {
+        match player {
+            PlayerKind::Black => self.black,
+            PlayerKind::White => self.white,
+        }
+    }
+}
+
+pub fn observation_outcome<R: Rng + ?Sized>(
+    game: &Game,
+    samples: usize,
+    rng: &mut R,
+) -> WinProbability {
+    outcome(game, samples, || game.sample_bits(rng))
+}
+
+pub fn cube_observation_outcome<R: Rng + ?Sized>(
+    game: &CubeGame,
+    samples: usize,
+    rng: &mut R,
+) -> WinProbability {
+    tally(samples, || {
+        let winners = cube::winners(game.lattice(), &game.sample(rng));
+        decide_winner(&winners, game.turn(), game.rules().tie_break)
+    })
+}
+
+pub fn region_observation_outcome<R: Rng + ?Sized>(
+    game: &Game,
+    region: Region,
+    samples: usize,
+    rng: &mut R,
+) -> WinProbability {
+    outcome(game, samples, || game.sample_region_bits(region, rng))
+}
+
+fn outcome(
+    game: &Game,
+    samples: usize,
+    mut sample: impl FnMut() -> ObservedBits,
+) -> WinProbability {
+    tally(samples, || {
+        let winners = sample().winners_on(game.rules().geometry());
+        decide_winner(&winners, game.turn(), game.rules().tie_break)
+    })
+}
+
+fn tally(samples: usize, mut winner: impl FnMut() -> Option<PlayerKind>) -> WinProbability {
+    let mut black = 0;
+    let mut white = 0;
+
+    for _ in 0..samples {
+        match winner() {
+            Some(PlayerKind::Black) => black += 1,
+            Some(PlayerKind::White) => white += 1,
+            None => (),
+        }
+    }
+
+    let samples = samples.max(1) as f64;
+    WinProbability {
+        black: black as f64 / samples,
+        white: white as f64 / samples,
+        none: 1.0 - (black + white) as f64 / samples,
+    }
+}
@@ -0,0 +1,13 @@
+use std::io;
+
+use quantum_gomoku::{ai::RandomAgent, protocol::EngineAdapter};
+
+fn main() -> io::Result<()> {
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random);
+
+    let mut adapter = EngineAdapter::new(RandomAgent::new(seed));
+    adapter.run(io::stdin().lock(), io::stdout().lock())
+}
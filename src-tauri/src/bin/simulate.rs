@@ -0,0 +1,163 @@
+use std::process;
+
+use quantum_gomoku::{
+    game::{Rules, TieBreak, Topology},
+    grid::Grid,
+    player::PColor,
+    protocol::{parse_decoherence, parse_handicap},
+    simulation::{simulate, SimulationConfig},
+    stone_model::StoneModelKind,
+};
+
+const USAGE: &str = "usage: simulate [--games N] [--seed SEED] [--max-plies N] \
+[--black AGENT] [--white AGENT] [--observe-keeps-turn] [--black-first strong|weak] \
+[--white-first strong|weak] [--tie-break observer|opponent|continue] \
+[--observation-limit N] [--entanglement] [--superposition] [--stone-model classical|amplitude] [--regional-observation] \
+[--persistent-collapse] [--decoherence even:STEP|certain:STEP] \
+[--influence N] [--certain-stones N] [--erasure N] [--teams] [--topology bounded|toroidal] \
+[--grid square|hex] [--gravity] \
+[--handicap black|white:N:certain|strong] [--threads N]
+
+AGENT is `random`, `greedy` or `engine:COMMAND` for an external protocol engine.";
+
+fn main() {
+    let mut config = SimulationConfig {
+        rules: Rules::default(),
+        black: "random".to_string(),
+        white: "random".to_string(),
+        games: 100_000,
+        seed: 0,
+        max_plies: 1000,
+    };
+    let mut threads = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| exit_with_usage());
+        match arg.as_str() {
+            "--games" => config.games = parse(&value()),
+            "--seed" => config.seed = parse(&value()),
+            "--max-plies" => config.max_plies = parse(&value()),
+            "--black" => config.black = value(),
+            "--white" => config.white = value(),
+            "--observe-keeps-turn" => config.rules.observe_passes_turn = false,
+            "--black-first" => config.rules.black_first_stone = parse_stone(&value()),
+            "--white-first" => config.rules.white_first_stone = parse_stone(&value()),
+            "--tie-break" => {
+                config.rules.tie_break = match value().as_str() {
+                    "observer" => TieBreak::Observer,
+                    "opponent" => TieBreak::Opponent,
+                    "continue" => TieBreak::Continue,
+                    _ => exit_with_usage(),
+                }
+            }
+            "--observation-limit" => config.rules.observation_limit = Some(parse(&value())),
+            "--entanglement" => config.rules.allow_entanglement = true,
+            "--superposition" => config.rules.allow_superposition = true,
+            "--stone-model" => {
+                config.rules.stone_model = match value().as_str() {
+                    "classical" => StoneModelKind::Classical,
+                    "amplitude" => StoneModelKind::Amplitude,
+                    _ => exit_with_usage(),
+                }
+            }
+            "--regional-observation" => config.rules.regional_observation = true,
+            "--persistent-collapse" => config.rules.persistent_collapse = true,
+            "--influence" => config.rules.influence = Some(parse(&value())),
+            "--certain-stones" => config.rules.certain_stones = parse(&value()),
+            "--erasure" => config.rules.erasure = Some(parse(&value())),
+            "--teams" => config.rules.teams = true,
+            "--gravity" => config.rules.gravity = true,
+            "--handicap" => {
+                config.rules.handicap =
+                    Some(parse_handicap(&value()).unwrap_or_else(|| exit_with_usage()))
+            }
+            "--topology" => {
+                config.rules.topology = match value().as_str() {
+                    "bounded" => Topology::Bounded,
+                    "toroidal" => Topology::Toroidal,
+                    _ => exit_with_usage(),
+                }
+            }
+            "--grid" => {
+                config.rules.grid = match value().as_str() {
+                    "square" => Grid::Square,
+                    "hex" => Grid::Hex,
+                    _ => exit_with_usage(),
+                }
+            }
+            "--decoherence" => {
+                config.rules.decoherence =
+                    Some(parse_decoherence(&value()).unwrap_or_else(|| exit_with_usage()))
+            }
+            "--threads" => threads = Some(parse(&value())),
+            _ => exit_with_usage(),
+        }
+    }
+
+    if let Err(err) = config.rules.validate() {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("failed to build thread pool");
+    }
+
+    let statistics = match simulate(&config) {
+        Ok(statistics) => statistics,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    println!("rules                   {:?}", config.rules);
+    println!(
+        "black / white           {} / {}",
+        config.black, config.white
+    );
+    println!("games                   {}", statistics.games);
+    println!(
+        "first player win rate   {:.4}",
+        statistics.first_player_win_rate()
+    );
+    println!(
+        "second player win rate  {:.4}",
+        statistics.second_player_win_rate()
+    );
+    println!("draw rate               {:.4}", statistics.draw_rate());
+    println!("average game length     {:.2}", statistics.average_length());
+    println!(
+        "observation frequency   {:.4}",
+        statistics.observation_frequency()
+    );
+    println!(
+        "tie-break frequency     {:.4}",
+        statistics.tie_break_frequency()
+    );
+    println!(
+        "double fives observed   {} in {} observations",
+        statistics.tie_breaks, statistics.observations
+    );
+}
+
+fn parse_stone(value: &str) -> PColor {
+    match value {
+        "strong" => PColor::Strong,
+        "weak" => PColor::Weak,
+        _ => exit_with_usage(),
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| exit_with_usage())
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
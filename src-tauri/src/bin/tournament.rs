@@ -0,0 +1,106 @@
+use std::{path::PathBuf, process};
+
+use quantum_gomoku::{
+    game::Rules,
+    protocol::parse_rules,
+    rating::{bayes_elo, elo},
+    tournament::{create_agent, Format, Tournament, TournamentConfig},
+};
+
+const USAGE: &str = "usage: tournament [--swiss ROUNDS] [--games N] [--seed SEED] \
+[--max-plies N] [--records DIR] [--rules RULES] AGENT AGENT...
+
+AGENT is `random`, `greedy` or `engine:COMMAND` for an external protocol engine.
+RULES is the argument of the protocol's RULES command, e.g. \"superposition=on teams=on\".";
+
+fn main() {
+    let mut config = TournamentConfig {
+        format: Format::RoundRobin,
+        rules: Rules::default(),
+        games_per_pairing: 2,
+        seed: 0,
+        max_plies: 1000,
+        record_dir: None,
+    };
+    let mut specs = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| exit_with_usage());
+        match arg.as_str() {
+            "--swiss" => {
+                config.format = Format::Swiss {
+                    rounds: parse(&value()),
+                }
+            }
+            "--games" => config.games_per_pairing = parse(&value()),
+            "--seed" => config.seed = parse(&value()),
+            "--max-plies" => config.max_plies = parse(&value()),
+            "--records" => config.record_dir = Some(PathBuf::from(value())),
+            "--rules" => {
+                config.rules = parse_rules(&value()).unwrap_or_else(|err| {
+                    eprintln!("--rules: {}", err);
+                    process::exit(2);
+                })
+            }
+            "--help" | "-h" => exit_with_usage(),
+            _ => specs.push(arg),
+        }
+    }
+
+    if specs.len() < 2 {
+        exit_with_usage();
+    }
+
+    let mut agents = Vec::new();
+    for (i, spec) in specs.iter().enumerate() {
+        match create_agent(spec, config.seed.wrapping_add(i as u64)) {
+            Ok(agent) => agents.push(agent),
+            Err(err) => {
+                eprintln!("{}: {}", spec, err);
+                process::exit(1);
+            }
+        }
+    }
+
+    let result = match Tournament::new(&mut agents, config).run() {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("failed to write game records: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let elo = elo(&result.results, result.names.len(), 16.0);
+    let bayes_elo = bayes_elo(&result.results, result.names.len());
+
+    println!(
+        "{:<4} {:<20} {:>6} {:>6} {:>6} {:>7} {:>7} {:>9}",
+        "#", "agent", "win", "draw", "loss", "score", "elo", "bayeselo"
+    );
+    let mut order: Vec<usize> = (0..result.names.len()).collect();
+    order.sort_by(|&a, &b| bayes_elo[b].total_cmp(&bayes_elo[a]));
+    for (rank, &i) in order.iter().enumerate() {
+        let standing = result.standings[i];
+        println!(
+            "{:<4} {:<20} {:>6} {:>6} {:>6} {:>7.1} {:>7.0} {:>9.0}",
+            rank + 1,
+            result.names[i],
+            standing.wins,
+            standing.draws,
+            standing.losses,
+            standing.score(),
+            elo[i],
+            bayes_elo[i]
+        );
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| exit_with_usage())
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
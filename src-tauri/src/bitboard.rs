@@ -0,0 +1,383 @@
+use std::{
+    collections::HashSet,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not},
+};
+
+use crate::{
+    game::{ObservedBoard, ProbabilityBoard, Topology, BOARD_SIZE},
+    grid::{Geometry, Grid},
+    player::{Color, PColor, PlayerKind},
+};
+
+// Each row carries one always-empty guard bit so that horizontal and diagonal
+// shifts never connect the last column of a row to the first one of the next.
+const STRIDE: usize = BOARD_SIZE + 1;
+const BITS: usize = STRIDE * BOARD_SIZE;
+const WORDS: usize = BITS.div_ceil(64);
+const LINE_SHIFTS: [usize; 4] = [1, STRIDE, STRIDE + 1, STRIDE - 1];
+const HEX_LINE_SHIFTS: [usize; 3] = [1, STRIDE, STRIDE - 1];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Bitboard([u64; WORDS]);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard([0; WORDS]);
+
+    // Every cell of the board, leaving the guard bits clear.
+    pub const FULL: Bitboard = {
+        let mut words = [0; WORDS];
+        let mut i = 0;
+        while i < BITS {
+            if i % STRIDE < BOARD_SIZE {
+                words[i / 64] |= 1 << (i % 64);
+            }
+            i += 1;
+        }
+        Bitboard(words)
+    };
+
+    fn index(x: usize, y: usize) -> usize {
+        debug_assert!(x < BOARD_SIZE && y < BOARD_SIZE);
+        y * STRIDE + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let i = Self::index(x, y);
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize) {
+        let i = Self::index(x, y);
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn clear(&mut self, x: usize, y: usize) {
+        let i = Self::index(x, y);
+        self.0[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.0.iter().enumerate().flat_map(|(w, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                let i = w * 64 + bit;
+                Some((i % STRIDE, i / STRIDE))
+            })
+        })
+    }
+
+    fn shift_down(&self, n: usize) -> Bitboard {
+        let words = n / 64;
+        let bits = n % 64;
+        let mut result = [0; WORDS];
+
+        for (i, word) in result.iter_mut().enumerate() {
+            let Some(&low) = self.0.get(i + words) else {
+                break;
+            };
+            *word = low >> bits;
+            if bits > 0 {
+                if let Some(&high) = self.0.get(i + words + 1) {
+                    *word |= high << (64 - bits);
+                }
+            }
+        }
+
+        Bitboard(result)
+    }
+
+    pub fn has_five(&self) -> bool {
+        self.has_five_along(&LINE_SHIFTS)
+    }
+
+    fn has_five_along(&self, shifts: &[usize]) -> bool {
+        if self.count() < 5 {
+            return false;
+        }
+
+        shifts.iter().any(|&shift| {
+            let two = *self & self.shift_down(shift);
+            let four = two & two.shift_down(2 * shift);
+            !(four & self.shift_down(4 * shift)).is_empty()
+        })
+    }
+
+    // The guard bits keep shifted lines from wrapping, so fives across the
+    // edges of a torus are followed cell by cell instead.
+    pub fn has_five_on(&self, geometry: Geometry) -> bool {
+        match (geometry.topology, geometry.grid) {
+            (Topology::Bounded, Grid::Square) => self.has_five(),
+            (Topology::Bounded, Grid::Hex) => self.has_five_along(&HEX_LINE_SHIFTS),
+            (Topology::Toroidal, _) => {
+                self.count() >= 5
+                    && self.iter().any(|(x, y)| {
+                        geometry.directions().iter().any(|&direction| {
+                            (1..5).all(|i| {
+                                geometry
+                                    .step(x, y, direction, i)
+                                    .is_some_and(|(nx, ny)| self.get(nx, ny))
+                            })
+                        })
+                    })
+            }
+        }
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(mut self, other: Bitboard) -> Bitboard {
+        self &= other;
+        self
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, other: Bitboard) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a &= b;
+        }
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(mut self, other: Bitboard) -> Bitboard {
+        self |= other;
+        self
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, other: Bitboard) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a |= b;
+        }
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Bitboard;
+
+    fn bitxor(mut self, other: Bitboard) -> Bitboard {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a ^= b;
+        }
+        self
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        self ^ Bitboard::FULL
+    }
+}
+
+pub const STONE_CLASSES: [Color; 6] = [
+    Color::Black(PColor::Strong),
+    Color::Black(PColor::Weak),
+    Color::White(PColor::Strong),
+    Color::White(PColor::Weak),
+    Color::Black(PColor::Certain),
+    Color::White(PColor::Certain),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct StoneBoard {
+    classes: [Bitboard; STONE_CLASSES.len()],
+}
+
+impl StoneBoard {
+    fn class_index(color: Color) -> usize {
+        STONE_CLASSES
+            .iter()
+            .position(|&class| class == color)
+            .expect("every color has a stone class")
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
+        STONE_CLASSES
+            .iter()
+            .zip(&self.classes)
+            .find(|(_, class)| class.get(x, y))
+            .map(|(&color, _)| color)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Option<Color>) {
+        for class in self.classes.iter_mut() {
+            class.clear(x, y);
+        }
+        if let Some(color) = color {
+            self.classes[Self::class_index(color)].set(x, y);
+        }
+    }
+
+    pub fn class(&self, color: Color) -> Bitboard {
+        self.classes[Self::class_index(color)]
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (Color, Bitboard)> + '_ {
+        STONE_CLASSES
+            .iter()
+            .copied()
+            .zip(self.classes.iter().copied())
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.classes
+            .iter()
+            .fold(Bitboard::EMPTY, |occupied, &class| occupied | class)
+    }
+
+    pub fn to_array(&self) -> ProbabilityBoard {
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (color, class) in self.classes() {
+            for (x, y) in class.iter() {
+                board[y][x] = Some(color);
+            }
+        }
+        board
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ObservedBits {
+    pub stones: Bitboard,
+    pub black: Bitboard,
+}
+
+impl ObservedBits {
+    pub fn get(&self, x: usize, y: usize) -> Option<PlayerKind> {
+        if !self.stones.get(x, y) {
+            None
+        } else if self.black.get(x, y) {
+            Some(PlayerKind::Black)
+        } else {
+            Some(PlayerKind::White)
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, kind: Option<PlayerKind>) {
+        self.stones.clear(x, y);
+        self.black.clear(x, y);
+        match kind {
+            Some(PlayerKind::Black) => {
+                self.stones.set(x, y);
+                self.black.set(x, y);
+            }
+            Some(PlayerKind::White) => self.stones.set(x, y),
+            None => (),
+        }
+    }
+
+    pub fn white(&self) -> Bitboard {
+        self.stones ^ self.black
+    }
+
+    pub fn has_five(&self, kind: PlayerKind) -> bool {
+        match kind {
+            PlayerKind::Black => self.black.has_five(),
+            PlayerKind::White => self.white().has_five(),
+        }
+    }
+
+    pub fn winners(&self) -> HashSet<PlayerKind> {
+        [PlayerKind::Black, PlayerKind::White]
+            .into_iter()
+            .filter(|&kind| self.has_five(kind))
+            .collect()
+    }
+
+    pub fn winners_on(&self, geometry: Geometry) -> HashSet<PlayerKind> {
+        [PlayerKind::Black, PlayerKind::White]
+            .into_iter()
+            .filter(|&kind| match kind {
+                PlayerKind::Black => self.black.has_five_on(geometry),
+                PlayerKind::White => self.white().has_five_on(geometry),
+            })
+            .collect()
+    }
+
+    pub fn to_array(&self) -> ObservedBoard {
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (x, y) in self.stones.iter() {
+            board[y][x] = self.get(x, y);
+        }
+        board
+    }
+}
+
+impl From<&ObservedBoard> for ObservedBits {
+    fn from(board: &ObservedBoard) -> Self {
+        let mut bits = ObservedBits::default();
+        for (y, row) in board.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                bits.set(x, y, cell);
+            }
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::game::scan_winners_on;
+
+    #[test]
+    fn complement_keeps_the_guard_bits_clear() {
+        assert_eq!(!Bitboard::EMPTY, Bitboard::FULL);
+        assert_eq!(Bitboard::FULL.count() as usize, BOARD_SIZE * BOARD_SIZE);
+        assert!((!Bitboard::FULL).is_empty());
+    }
+
+    #[test]
+    fn five_detection_agrees_with_the_cell_by_cell_scan() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            for grid in [Grid::Square, Grid::Hex] {
+                let geometry = Geometry { grid, topology };
+                for density in [0.2, 0.4, 0.6, 0.8] {
+                    for _ in 0..200 {
+                        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+                        for cell in board.iter_mut().flatten() {
+                            if rng.gen::<f64>() < density {
+                                *cell = Some(if rng.gen() {
+                                    PlayerKind::Black
+                                } else {
+                                    PlayerKind::White
+                                });
+                            }
+                        }
+
+                        assert_eq!(
+                            ObservedBits::from(&board).winners_on(geometry),
+                            scan_winners_on(&board, geometry),
+                            "{:?}",
+                            geometry
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
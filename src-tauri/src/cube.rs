@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    game::{decide_winner, GameError, TieBreak, BOARD_SIZE},
+    grid::{Lattice, Point},
+    player::{Color, PColor, Player, PlayerKind},
+};
+
+// Indexed by `Lattice::index`.
+pub type CubeBoard = Vec<Option<PlayerKind>>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CubeRules {
+    pub lattice: Lattice,
+    pub observe_passes_turn: bool,
+    pub black_first_stone: PColor,
+    pub white_first_stone: PColor,
+    pub tie_break: TieBreak,
+    pub observation_limit: Option<u8>,
+}
+
+impl Default for CubeRules {
+    fn default() -> Self {
+        CubeRules {
+            lattice: Lattice::cube(7),
+            observe_passes_turn: true,
+            black_first_stone: PColor::Weak,
+            white_first_stone: PColor::Strong,
+            tie_break: TieBreak::Observer,
+            observation_limit: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CubeMove {
+    Place { point: Point, color: Color },
+    Observe(CubeBoard),
+}
+
+#[derive(Clone)]
+pub struct CubeGame {
+    rules: CubeRules,
+    stones: Vec<Option<Color>>,
+    turn: PlayerKind,
+    black: Player,
+    white: Player,
+    winner: Option<PlayerKind>,
+    history: Vec<CubeMove>,
+}
+
+impl CubeGame {
+    pub fn with_rules(rules: CubeRules) -> Result<CubeGame, GameError> {
+        let lattice = rules.lattice;
+        if !(5..=BOARD_SIZE).contains(&lattice.size)
+            || !(2..=lattice.size).contains(&lattice.layers)
+        {
+            return Err(GameError::InvalidLattice(lattice));
+        }
+
+        Ok(CubeGame {
+            rules,
+            stones: vec![None; lattice.cells()],
+            turn: PlayerKind::Black,
+            black: Player::new(PlayerKind::Black, rules.black_first_stone)
+                .with_observation_limit(rules.observation_limit),
+            white: Player::new(PlayerKind::White, rules.white_first_stone)
+                .with_observation_limit(rules.observation_limit),
+            winner: None,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn rules(&self) -> &CubeRules {
+        &self.rules
+    }
+
+    pub fn lattice(&self) -> Lattice {
+        self.rules.lattice
+    }
+
+    pub fn turn(&self) -> PlayerKind {
+        self.turn
+    }
+
+    pub fn winner(&self) -> Option<PlayerKind> {
+        self.winner
+    }
+
+    pub fn history(&self) -> &[CubeMove] {
+        &self.history
+    }
+
+    pub fn player(&self, kind: PlayerKind) -> &Player {
+        match kind {
+            PlayerKind::Black => &self.black,
+            PlayerKind::White => &self.white,
+        }
+    }
+
+    fn player_mut(&mut self, kind: PlayerKind) -> &mut Player {
+        match kind {
+            PlayerKind::Black => &mut self.black,
+            PlayerKind::White => &mut self.white,
+        }
+    }
+
+    pub fn stone(&self, point: Point) -> Option<Color> {
+        if !self.lattice().contains(point) {
+            return None;
+        }
+
+        self.stones[self.lattice().index(point)]
+    }
+
+    pub fn get_turn_p(&self) -> u8 {
+        match self.turn {
+            PlayerKind::Black => Color::Black(self.black.next_stone()),
+            PlayerKind::White => Color::White(self.white.next_stone()),
+        }
+        .into()
+    }
+
+    pub fn legal_placements(&self) -> Vec<Point> {
+        (0..self.lattice().cells())
+            .filter(|&index| self.stones[index].is_none())
+            .map(|index| self.lattice().point(index))
+            .collect()
+    }
+
+    pub fn can_observe(&self) -> bool {
+        self.winner.is_none() && self.player(self.turn).can_observe()
+    }
+
+    pub fn place_stone(&mut self, point: Point) -> Result<Color, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.lattice().contains(point) {
+            return Err(GameError::InvalidPosition(None));
+        }
+
+        if let Some(stone) = self.stone(point) {
+            return Err(GameError::InvalidPosition(Some(stone)));
+        }
+
+        let stone = self.player_mut(self.turn).consume_stone();
+        let color = match self.turn {
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
+        };
+
+        let index = self.lattice().index(point);
+        self.stones[index] = Some(color);
+        self.history.push(CubeMove::Place { point, color });
+
+        self.turn = self.turn.opponent();
+
+        Ok(color)
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CubeBoard {
+        self.stones
+            .iter()
+            .map(|stone| {
+                stone.map(|color| {
+                    let random_number = (rng.gen::<f64>() * 100.0).floor() as u8;
+                    if random_number < u8::from(color) {
+                        PlayerKind::Black
+                    } else {
+                        PlayerKind::White
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub fn observe(&mut self) -> Result<CubeBoard, GameError> {
+        self.observe_with(&mut thread_rng())
+    }
+
+    pub fn observe_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<CubeBoard, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.can_observe() {
+            return Err(GameError::NoObservationsLeft);
+        }
+
+        let who_observed = self.turn;
+        self.player_mut(who_observed).consume_observation();
+
+        if self.rules.observe_passes_turn {
+            self.turn = self.turn.opponent();
+        }
+
+        let board = self.sample(rng);
+        self.history.push(CubeMove::Observe(board.clone()));
+
+        let winners = winners(self.lattice(), &board);
+        self.winner = decide_winner(&winners, who_observed, self.rules.tie_break);
+
+        Ok(board)
+    }
+}
+
+pub fn winners(lattice: Lattice, board: &CubeBoard) -> HashSet<PlayerKind> {
+    [PlayerKind::Black, PlayerKind::White]
+        .into_iter()
+        .filter(|&kind| {
+            let marked: Vec<bool> = board.iter().map(|&cell| cell == Some(kind)).collect();
+            lattice.has_five(&marked)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_with(lattice: Lattice, points: impl Iterator<Item = Point>) -> CubeBoard {
+        let mut board = vec![None; lattice.cells()];
+        for point in points {
+            board[lattice.index(point)] = Some(PlayerKind::Black);
+        }
+        board
+    }
+
+    #[test]
+    fn a_single_layer_is_not_a_cube() {
+        let rules = |layers| CubeRules {
+            lattice: Lattice { size: 7, layers },
+            ..CubeRules::default()
+        };
+        assert!(matches!(
+            CubeGame::with_rules(rules(1)),
+            Err(GameError::InvalidLattice(_))
+        ));
+        assert!(CubeGame::with_rules(rules(2)).is_ok());
+    }
+
+    #[test]
+    fn fives_run_through_the_layers() {
+        let lattice = Lattice::cube(7);
+
+        let vertical = board_with(lattice, (1..6).map(|z| [3, 2, z]));
+        assert_eq!(
+            winners(lattice, &vertical),
+            HashSet::from([PlayerKind::Black])
+        );
+
+        let space_diagonal = board_with(lattice, (0..5).map(|i| [i + 1, 5 - i, i]));
+        assert_eq!(
+            winners(lattice, &space_diagonal),
+            HashSet::from([PlayerKind::Black])
+        );
+
+        let broken = board_with(lattice, [1, 2, 3, 4, 6].map(|z| [3, 2, z]).into_iter());
+        assert!(winners(lattice, &broken).is_empty());
+    }
+}
@@ -1,51 +1,556 @@
 use std::{collections::HashSet, fmt::Display};
 
-use rand::random;
+use rand::{thread_rng, Rng};
 
-use crate::player::{Color, PColor, Player, PlayerKind};
+use crate::{
+    bitboard::{Bitboard, ObservedBits, StoneBoard},
+    grid::{Geometry, Grid, Lattice},
+    lines::LineTracker,
+    player::{Color, PColor, Player, PlayerKind},
+    position::Position,
+    stone_model::{StoneModel, StoneModelKind},
+    superposition::{self, Superposition},
+    zobrist,
+};
 
 pub const BOARD_SIZE: usize = 18;
 
 pub type ProbabilityBoard = [[Option<Color>; BOARD_SIZE]; BOARD_SIZE];
 pub type ObservedBoard = [[Option<PlayerKind>; BOARD_SIZE]; BOARD_SIZE];
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Place(usize, usize),
+    PlaceEntangled(Entanglement),
+    PlaceSuperposed([(usize, usize); 2]),
+    PlaceInfluencer(usize, usize),
+    PlaceCertain(usize, usize),
+    Flip(usize, usize),
+    Erase(usize, usize),
+    Observe,
+    ObserveRegion(Region),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Move {
+    Place {
+        x: usize,
+        y: usize,
+        color: Color,
+    },
+    PlaceEntangled {
+        entanglement: Entanglement,
+        colors: (Color, Color),
+    },
+    PlaceSuperposed(Superposition),
+    PlaceInfluencer {
+        x: usize,
+        y: usize,
+        color: Color,
+    },
+    Flip {
+        x: usize,
+        y: usize,
+    },
+    Erase {
+        x: usize,
+        y: usize,
+        color: Color,
+    },
+    Observe(Box<ObservedBoard>),
+    ObserveRegion {
+        region: Region,
+        board: Box<ObservedBoard>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Empty,
+    Stone(Color),
+    Superposed(Vec<Superposition>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Correlation {
+    Correlated,
+    Anticorrelated,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Entanglement {
+    pub first: (usize, usize),
+    pub second: (usize, usize),
+    pub correlation: Correlation,
+}
+
+impl Entanglement {
+    // Probabilities indexed by [first is black][second is black], given the
+    // first stone's chance of being black. The pair only ever collapses to one
+    // of two outcomes, unlike two independent stones.
+    pub fn joint_distribution(&self, first_black: f64) -> [[f64; 2]; 2] {
+        let p = first_black;
+        match self.correlation {
+            Correlation::Correlated => [[1.0 - p, 0.0], [0.0, p]],
+            Correlation::Anticorrelated => [[0.0, 1.0 - p], [p, 0.0]],
+        }
+    }
+
+    pub fn second_color(&self, first: Color) -> Color {
+        match self.correlation {
+            Correlation::Correlated => first,
+            Correlation::Anticorrelated => first.complement(),
+        }
+    }
+}
+
+pub const WINDOW_RADIUS: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Region {
+    Window(usize, usize),
+    Row(usize),
+    Column(usize),
+    Diagonal(usize, usize),
+    AntiDiagonal(usize, usize),
+}
+
+// On the hex grid rows, columns and anti-diagonals are its three lines, and a
+// window is the hexagon of cells within `WINDOW_RADIUS` steps. Diagonals cut
+// across the hex lines, so there is no diagonal region there.
+impl Region {
+    pub fn is_valid(&self, grid: Grid) -> bool {
+        let (x, y) = match *self {
+            Region::Row(y) => (0, y),
+            Region::Column(x) => (x, 0),
+            Region::Diagonal(_, _) if grid == Grid::Hex => return false,
+            Region::Window(x, y) | Region::Diagonal(x, y) | Region::AntiDiagonal(x, y) => (x, y),
+        };
+        x < BOARD_SIZE && y < BOARD_SIZE
+    }
+
+    pub fn contains(&self, x: usize, y: usize, grid: Grid) -> bool {
+        match *self {
+            Region::Window(cx, cy) if grid == Grid::Hex => {
+                let dq = x as isize - cx as isize;
+                let dr = y as isize - cy as isize;
+                (dq.abs() + dr.abs() + (dq + dr).abs()) as usize / 2 <= WINDOW_RADIUS
+            }
+            Region::Window(cx, cy) => {
+                cx.abs_diff(x) <= WINDOW_RADIUS && cy.abs_diff(y) <= WINDOW_RADIUS
+            }
+            Region::Row(row) => y == row,
+            Region::Column(column) => x == column,
+            Region::Diagonal(cx, cy) => x as isize - y as isize == cx as isize - cy as isize,
+            Region::AntiDiagonal(cx, cy) => x + y == cx + cy,
+        }
+    }
+
+    pub fn mask(&self, grid: Grid) -> Bitboard {
+        let mut mask = Bitboard::EMPTY;
+        for (x, y) in Bitboard::FULL
+            .iter()
+            .filter(|&(x, y)| self.contains(x, y, grid))
+        {
+            mask.set(x, y);
+        }
+        mask
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TieBreak {
+    Observer,
+    Opponent,
+    Continue,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Topology {
+    #[default]
+    Bounded,
+    Toroidal,
+}
+
+impl Topology {
+    // The cell `steps` cells away along `direction`. On a torus lines that run
+    // off one edge carry on from the opposite one, so there always is one.
+    pub fn step(
+        &self,
+        x: usize,
+        y: usize,
+        direction: (isize, isize),
+        steps: isize,
+    ) -> Option<(usize, usize)> {
+        let nx = x as isize + direction.0 * steps;
+        let ny = y as isize + direction.1 * steps;
+        let size = BOARD_SIZE as isize;
+
+        match self {
+            Topology::Bounded => ((0..size).contains(&nx) && (0..size).contains(&ny))
+                .then_some((nx as usize, ny as usize)),
+            Topology::Toroidal => {
+                Some((nx.rem_euclid(size) as usize, ny.rem_euclid(size) as usize))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Drift {
+    Even,
+    Certain,
+}
+
+// Every turn that passes without an observation, each stone that is not
+// certain moves `step` percentage points towards 50%, or away from it towards
+// 100% or 0%. A stone starts drifting on the turn after it was placed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Decoherence {
+    pub toward: Drift,
+    pub step: u8,
+}
+
+impl Decoherence {
+    pub fn drift(&self, p: u8) -> u8 {
+        match self.toward {
+            Drift::Even if p > 50 => p.saturating_sub(self.step).max(50),
+            Drift::Even => p.saturating_add(self.step).min(50),
+            Drift::Certain if p > 50 => p.saturating_add(self.step).min(100),
+            Drift::Certain if p < 50 => p.saturating_sub(self.step),
+            Drift::Certain => p,
+        }
+    }
+}
+
+pub const MAX_HANDICAP: u8 = 9;
+
+// The weaker side starts with `stones` stones of strength `stone` on the star
+// points, and the stronger side moves first.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Handicap {
+    pub player: PlayerKind,
+    pub stones: u8,
+    pub stone: PColor,
+}
+
+impl Handicap {
+    pub fn color(&self) -> Color {
+        match self.player {
+            PlayerKind::Black => Color::Black(self.stone),
+            PlayerKind::White => Color::White(self.stone),
+        }
+    }
+
+    // The usual patterns on the star points: corners first, then the sides,
+    // with the centre taking a lone stone and odd counts from five up.
+    pub fn points(&self) -> Vec<(usize, usize)> {
+        let (near, far, middle) = (3, BOARD_SIZE - 4, BOARD_SIZE / 2);
+        let corners = [(far, near), (near, far), (far, far), (near, near)];
+        let sides = [(near, middle), (far, middle), (middle, near), (middle, far)];
+
+        let stones = self.stones.min(MAX_HANDICAP) as usize;
+        let centre = stones == 1 || (stones >= 5 && stones % 2 == 1);
+        let mut points: Vec<(usize, usize)> = corners
+            .into_iter()
+            .chain(sides)
+            .take(stones - usize::from(centre))
+            .collect();
+        if centre {
+            points.push((middle, middle));
+        }
+        points
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rules {
+    pub observe_passes_turn: bool,
+    pub black_first_stone: PColor,
+    pub white_first_stone: PColor,
+    pub tie_break: TieBreak,
+    pub observation_limit: Option<u8>,
+    pub allow_entanglement: bool,
+    pub allow_superposition: bool,
+    pub stone_model: StoneModelKind,
+    pub regional_observation: bool,
+    pub persistent_collapse: bool,
+    pub decoherence: Option<Decoherence>,
+    pub influence: Option<u8>,
+    pub certain_stones: u8,
+    pub erasure: Option<u8>,
+    pub teams: bool,
+    pub topology: Topology,
+    pub grid: Grid,
+    pub gravity: bool,
+    pub handicap: Option<Handicap>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            observe_passes_turn: true,
+            black_first_stone: PColor::Weak,
+            white_first_stone: PColor::Strong,
+            tie_break: TieBreak::Observer,
+            observation_limit: None,
+            allow_entanglement: false,
+            allow_superposition: false,
+            stone_model: StoneModelKind::Classical,
+            regional_observation: false,
+            persistent_collapse: false,
+            decoherence: None,
+            influence: None,
+            certain_stones: 0,
+            erasure: None,
+            teams: false,
+            topology: Topology::Bounded,
+            grid: Grid::Square,
+            gravity: false,
+            handicap: None,
+        }
+    }
+}
+
+impl Rules {
+    pub fn geometry(&self) -> Geometry {
+        Geometry {
+            grid: self.grid,
+            topology: self.topology,
+        }
+    }
+
+    pub fn first_player(&self) -> PlayerKind {
+        match self.handicap {
+            Some(handicap) => handicap.player.opponent(),
+            None => PlayerKind::Black,
+        }
+    }
+
+    // Variants that cannot be played together.
+    pub fn validate(&self) -> Result<(), GameError> {
+        // Erasing a stone would leave the stones above it floating.
+        if self.gravity && self.erasure.is_some() {
+            return Err(GameError::IncompatibleRules("gravity", "erasure"));
+        }
+        // A superposed stone settles on one of its cells, and would leave the
+        // other one empty under whatever was dropped on it.
+        if self.gravity && self.allow_superposition {
+            return Err(GameError::IncompatibleRules("gravity", "superposition"));
+        }
+
+        // Columns only fall straight down on the square grid; on the hex grid
+        // they run at a slant.
+        if self.gravity && self.grid == Grid::Hex {
+            return Err(GameError::IncompatibleRules("gravity", "hex"));
+        }
+
+        // Handicap stones sit on the star points, off the bottom row.
+        if self.gravity && self.handicap.is_some() {
+            return Err(GameError::IncompatibleRules("gravity", "handicap"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
 pub struct Game {
-    p_board: ProbabilityBoard,
-    observed_board: ObservedBoard,
+    rules: Rules,
+
+    p_board: StoneBoard,
+    model: Box<dyn StoneModel>,
+    entanglements: Vec<Entanglement>,
+    superpositions: Vec<Superposition>,
+    superposed: Bitboard,
+    influencers: Bitboard,
+    lines: LineTracker,
 
     turn: PlayerKind,
 
     black: Player,
     white: Player,
+    // The waiting teammate of each side in a team game.
+    reserve: Option<[Player; 2]>,
 
     winner: Option<PlayerKind>,
+    history: Vec<Move>,
+    hash: u64,
+    // Where the game started when it was set up in the editor.
+    start: Option<Box<Position>>,
 }
 
 impl Game {
     pub fn new() -> Game {
-        Game {
-            p_board: [[None; BOARD_SIZE]; BOARD_SIZE],
-            observed_board: [[None; BOARD_SIZE]; BOARD_SIZE],
-            turn: PlayerKind::Black,
-            black: Player::new(PlayerKind::Black, PColor::Weak),
-            white: Player::new(PlayerKind::White, PColor::Strong),
+        Game::with_rules(Rules::default())
+    }
+
+    pub fn with_rules(rules: Rules) -> Game {
+        let black = Player::new(PlayerKind::Black, rules.black_first_stone)
+            .with_observation_limit(rules.observation_limit)
+            .with_certain_stones(rules.certain_stones);
+        let white = Player::new(PlayerKind::White, rules.white_first_stone)
+            .with_observation_limit(rules.observation_limit)
+            .with_certain_stones(rules.certain_stones);
+        let reserve = rules
+            .teams
+            .then(|| [black.clone().with_member(1), white.clone().with_member(1)]);
+
+        let mut game = Game {
+            rules,
+            p_board: StoneBoard::default(),
+            model: rules.stone_model.create(),
+            entanglements: Vec::new(),
+            superpositions: Vec::new(),
+            superposed: Bitboard::EMPTY,
+            influencers: Bitboard::EMPTY,
+            lines: LineTracker::with_geometry(rules.geometry()),
+            turn: rules.first_player(),
+            black,
+            white,
+            reserve,
             winner: None,
+            history: Vec::new(),
+            hash: 0,
+            start: None,
+        };
+        if let Some(handicap) = rules.handicap {
+            for (x, y) in handicap.points() {
+                game.p_board.set(x, y, Some(handicap.color()));
+                game.model.place(x, y, handicap.color());
+            }
+        }
+        game.hash = zobrist::hash(&game);
+        game
+    }
+
+    // The handicap is dropped, as the position already has every stone.
+    pub fn from_position(rules: Rules, position: &Position) -> Result<Game, GameError> {
+        position.validate(&rules)?;
+
+        let rules = Rules {
+            handicap: None,
+            ..rules
+        };
+        let mut game = Game::with_rules(Rules {
+            black_first_stone: position.next_stone(PlayerKind::Black),
+            white_first_stone: position.next_stone(PlayerKind::White),
+            ..rules
+        });
+        game.rules = rules;
+
+        for (color, class) in position.stones().classes() {
+            for (x, y) in class.iter() {
+                game.p_board.set(x, y, Some(color));
+                game.model.place(x, y, color);
+            }
         }
+        game.turn = position.turn();
+        game.start = Some(Box::new(*position));
+        game.hash = zobrist::hash(&game);
+
+        Ok(game)
+    }
+
+    pub fn start_position(&self) -> Option<&Position> {
+        self.start.as_deref()
     }
 
     pub fn reset(&mut self) {
-        self.p_board = [[None; BOARD_SIZE]; BOARD_SIZE];
-        self.observed_board = [[None; BOARD_SIZE]; BOARD_SIZE];
-        self.turn = PlayerKind::Black;
-        self.black = Player::new(PlayerKind::Black, PColor::Weak);
-        self.white = Player::new(PlayerKind::White, PColor::Strong);
-        self.winner = None;
+        *self = match self.start.take() {
+            Some(start) => {
+                Game::from_position(self.rules, &start).expect("the start position was validated")
+            }
+            None => Game::with_rules(self.rules),
+        };
     }
 
-    pub fn get_p_board(&self) -> &[[Option<Color>; BOARD_SIZE]; BOARD_SIZE] {
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn get_p_board(&self) -> ProbabilityBoard {
+        self.p_board.to_array()
+    }
+
+    pub fn stones(&self) -> &StoneBoard {
         &self.p_board
     }
 
+    pub fn stone(&self, x: usize, y: usize) -> Option<Color> {
+        self.p_board.get(x, y)
+    }
+
+    pub fn model(&self) -> &dyn StoneModel {
+        self.model.as_ref()
+    }
+
+    pub fn black_probability(&self, x: usize, y: usize) -> Option<f64> {
+        self.p_board
+            .get(x, y)
+            .map(|color| self.model.black_probability(x, y, color))
+    }
+
+    pub fn entanglements(&self) -> &[Entanglement] {
+        &self.entanglements
+    }
+
+    pub fn superpositions(&self) -> &[Superposition] {
+        &self.superpositions
+    }
+
+    pub fn superposed_black_probability(&self, index: usize) -> f64 {
+        self.model.superposed_black_probability(index)
+    }
+
+    pub fn influencers(&self) -> Bitboard {
+        self.influencers
+    }
+
+    // Stones that observation may still turn either way.
+    pub fn uncertain_stones(&self) -> Bitboard {
+        let mut stones = Bitboard::EMPTY;
+        for (x, y) in self.p_board.occupied().iter() {
+            if self
+                .black_probability(x, y)
+                .is_some_and(|p| p > 0.0 && p < 1.0)
+            {
+                stones.set(x, y);
+            }
+        }
+        stones
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Cell {
+        if let Some(color) = self.p_board.get(x, y) {
+            return Cell::Stone(color);
+        }
+
+        if !self.superposed.get(x, y) {
+            return Cell::Empty;
+        }
+
+        Cell::Superposed(
+            self.superpositions
+                .iter()
+                .filter(|stone| stone.contains(x, y))
+                .copied()
+                .collect(),
+        )
+    }
+
+    pub fn lines(&self) -> &LineTracker {
+        &self.lines
+    }
+
+    pub fn get_observed_board(&self) -> ObservedBoard {
+        self.lines.board().to_array()
+    }
+
+    pub fn observed(&self) -> &ObservedBits {
+        self.lines.board()
+    }
+
     pub fn is_valid_position(&self, x: usize, y: usize) -> bool {
         self.check_position(x, y, None)
     }
@@ -54,28 +559,154 @@ impl Game {
         self.turn
     }
 
+    pub fn player(&self, kind: PlayerKind) -> &Player {
+        match kind {
+            PlayerKind::Black => &self.black,
+            PlayerKind::White => &self.white,
+        }
+    }
+
+    pub fn teammate(&self, kind: PlayerKind) -> Option<&Player> {
+        let reserve = self.reserve.as_ref()?;
+        Some(match kind {
+            PlayerKind::Black => &reserve[0],
+            PlayerKind::White => &reserve[1],
+        })
+    }
+
+    fn update_player<T>(&mut self, kind: PlayerKind, f: impl FnOnce(&mut Player) -> T) -> T {
+        let player = match kind {
+            PlayerKind::Black => &mut self.black,
+            PlayerKind::White => &mut self.white,
+        };
+
+        let before = zobrist::player_key(player);
+        let result = f(player);
+        self.hash ^= before ^ zobrist::player_key(player);
+
+        result
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn can_observe(&self) -> bool {
+        self.winner.is_none() && self.player(self.turn).can_observe()
+    }
+
+    pub fn winner(&self) -> Option<PlayerKind> {
+        self.winner
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
     pub fn get_turn_p(&self) -> u8 {
         match self.turn {
-            PlayerKind::Black => match self.black.next_stone() {
-                PColor::Strong => 90,
-                PColor::Weak => 70,
-            },
-            PlayerKind::White => match self.white.next_stone() {
-                PColor::Strong => 10,
-                PColor::Weak => 30,
-            },
+            PlayerKind::Black => Color::Black(self.black.next_stone()),
+            PlayerKind::White => Color::White(self.white.next_stone()),
         }
+        .into()
+    }
+
+    pub fn legal_placements(&self) -> Vec<(usize, usize)> {
+        if self.rules.gravity {
+            return (0..BOARD_SIZE)
+                .filter_map(|column| self.drop_position(column))
+                .collect();
+        }
+
+        (!(self.p_board.occupied() | self.superposed))
+            .iter()
+            .collect()
+    }
+
+    // Under gravity a stone goes to the lowest free cell of its column, the
+    // bottom row being y = BOARD_SIZE - 1.
+    pub fn drop_position(&self, column: usize) -> Option<(usize, usize)> {
+        if column >= BOARD_SIZE {
+            return None;
+        }
+
+        (0..BOARD_SIZE)
+            .rev()
+            .find(|&y| self.p_board.get(column, y).is_none() && !self.superposed.get(column, y))
+            .map(|y| (column, y))
     }
 
     fn check_position(&self, x: usize, y: usize, value: Option<Color>) -> bool {
-        (0..BOARD_SIZE).contains(&x) && (0..BOARD_SIZE).contains(&y) && self.p_board[y][x] == value
+        (0..BOARD_SIZE).contains(&x)
+            && (0..BOARD_SIZE).contains(&y)
+            && self.p_board.get(x, y) == value
+            && (value.is_some() || !self.superposed.get(x, y))
+            && (value.is_some() || !self.rules.gravity || self.drop_position(x) == Some((x, y)))
     }
 
     fn take_turn(&mut self) {
-        self.turn = match self.turn {
-            PlayerKind::Black => PlayerKind::White,
-            PlayerKind::White => PlayerKind::Black,
-        };
+        // In a team game the side that just moved hands over to its other
+        // player, so turns go A1, B1, A2, B2.
+        if let Some(reserve) = self.reserve.as_mut() {
+            let (active, waiting) = match self.turn {
+                PlayerKind::Black => (&mut self.black, &mut reserve[0]),
+                PlayerKind::White => (&mut self.white, &mut reserve[1]),
+            };
+            self.hash ^= zobrist::player_key(active) ^ zobrist::reserve_key(waiting);
+            std::mem::swap(active, waiting);
+            self.hash ^= zobrist::player_key(active) ^ zobrist::reserve_key(waiting);
+        }
+
+        self.hash ^= zobrist::side_key(self.turn);
+        self.turn = self.turn.opponent();
+        self.hash ^= zobrist::side_key(self.turn);
+    }
+
+    // A turn that passes without an observation decoheres every stone that
+    // is not certain, except those `placed` on it.
+    fn pass_turn(&mut self, placed: &[(usize, usize)]) {
+        if let Some(decoherence) = self.rules.decoherence {
+            let mut stones = self.uncertain_stones();
+            for &(x, y) in placed {
+                stones.clear(x, y);
+            }
+            self.hash ^= self.model_key(stones);
+            self.model.decohere(stones, decoherence);
+            self.hash ^= self.model_key(stones);
+
+            // A stone superposed this turn is the last one, and waits too.
+            let mut count = self.superpositions.len();
+            if matches!(self.history.last(), Some(Move::PlaceSuperposed(_))) {
+                count -= 1;
+            }
+            self.hash ^= self.superposed_model_key();
+            self.model.decohere_superposed(count, decoherence);
+            self.hash ^= self.superposed_model_key();
+        }
+
+        self.take_turn();
+    }
+
+    pub fn apply_action(&mut self, action: Action) -> Result<(), GameError> {
+        self.apply_action_with(action, &mut thread_rng())
+    }
+
+    pub fn apply_action_with<R: Rng + ?Sized>(
+        &mut self,
+        action: Action,
+        rng: &mut R,
+    ) -> Result<(), GameError> {
+        match action {
+            Action::Place(x, y) => self.place_stone_prohibitly(x, y).map(|_| ()),
+            Action::PlaceEntangled(entanglement) => self.place_entangled(entanglement).map(|_| ()),
+            Action::PlaceSuperposed(cells) => self.place_superposed(cells).map(|_| ()),
+            Action::PlaceInfluencer(x, y) => self.place_influencer(x, y).map(|_| ()),
+            Action::PlaceCertain(x, y) => self.place_certain(x, y).map(|_| ()),
+            Action::Flip(x, y) => self.flip(x, y),
+            Action::Erase(x, y) => self.erase(x, y).map(|_| ()),
+            Action::Observe => self.try_observe_with(rng).map(|_| ()),
+            Action::ObserveRegion(region) => self.try_observe_region_with(region, rng).map(|_| ()),
+        }
     }
 
     pub fn place_stone_prohibitly(&mut self, x: usize, y: usize) -> Result<Color, GameError> {
@@ -87,105 +718,730 @@ impl Game {
             return Err(GameError::InvalidPosition(None));
         }
 
+        if let Some(stone) = self.p_board.get(x, y) {
+            return Err(GameError::InvalidPosition(Some(stone)));
+        }
+
+        let stone = self.update_player(self.turn, Player::consume_stone);
+        let color = match self.turn {
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
+        };
+
+        self.p_board.set(x, y, Some(color));
+        self.model.place(x, y, color);
+        self.hash ^= zobrist::stone_key(x, y, color) ^ self.model.key(x, y);
+        self.history.push(Move::Place { x, y, color });
+
+        self.pass_turn(&[(x, y)]);
+
+        Ok(color)
+    }
+
+    pub fn drop_stone(&mut self, column: usize) -> Result<(usize, usize, Color), GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.rules.gravity {
+            return Err(GameError::GravityNotEnabled);
+        }
+
+        let (x, y) = self
+            .drop_position(column)
+            .ok_or(GameError::ColumnFull(column))?;
+        let color = self.place_stone_prohibitly(x, y)?;
+
+        Ok((x, y, color))
+    }
+
+    pub fn place_certain(&mut self, x: usize, y: usize) -> Result<Color, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.player(self.turn).can_place_certain() {
+            return Err(GameError::NoCertainStonesLeft);
+        }
+
+        if !self.is_valid_position(x, y) {
+            return Err(GameError::InvalidPosition(None));
+        }
+
+        let stone = self.update_player(self.turn, Player::consume_certain_stone);
         let color = match self.turn {
-            PlayerKind::Black => Color::Black(self.black.consume_stone()),
-            PlayerKind::White => Color::White(self.white.consume_stone()),
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
         };
 
-        if self.p_board[y][x].is_some() {
-            return Err(GameError::InvalidPosition(self.p_board[y][x]));
+        self.p_board.set(x, y, Some(color));
+        self.model.place(x, y, color);
+        self.hash ^= zobrist::stone_key(x, y, color) ^ self.model.key(x, y);
+        self.history.push(Move::Place { x, y, color });
+
+        self.pass_turn(&[(x, y)]);
+
+        Ok(color)
+    }
+
+    pub fn place_entangled(
+        &mut self,
+        entanglement: Entanglement,
+    ) -> Result<(Color, Color), GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
         }
 
-        self.p_board[y][x] = Some(color);
+        if !self.rules.allow_entanglement {
+            return Err(GameError::EntanglementNotAllowed);
+        }
 
-        let mut debug_count = 0;
-        for line in self.p_board.iter() {
-            for cell in line.iter() {
-                if cell.is_some() {
-                    debug_count += 1;
-                }
+        let (x1, y1) = entanglement.first;
+        let (x2, y2) = entanglement.second;
+        if entanglement.first == entanglement.second
+            || !self.is_valid_position(x1, y1)
+            || !self.is_valid_position(x2, y2)
+        {
+            return Err(GameError::InvalidPosition(None));
+        }
+
+        let stone = self.update_player(self.turn, Player::consume_stone);
+        let first = match self.turn {
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
+        };
+        let second = entanglement.second_color(first);
+
+        self.p_board.set(x1, y1, Some(first));
+        self.p_board.set(x2, y2, Some(second));
+        self.model.place(x1, y1, first);
+        self.model.place(x2, y2, second);
+        self.hash ^= zobrist::stone_key(x1, y1, first) ^ zobrist::stone_key(x2, y2, second);
+        self.hash ^= self.model.key(x1, y1) ^ self.model.key(x2, y2);
+        self.hash ^= zobrist::entanglement_key(&entanglement);
+
+        self.entanglements.push(entanglement);
+        self.history.push(Move::PlaceEntangled {
+            entanglement,
+            colors: (first, second),
+        });
+
+        self.pass_turn(&[(x1, y1), (x2, y2)]);
+
+        Ok((first, second))
+    }
+
+    pub fn can_superpose(&self, cells: [(usize, usize); 2]) -> bool {
+        let inside = cells
+            .iter()
+            .all(|&(x, y)| (0..BOARD_SIZE).contains(&x) && (0..BOARD_SIZE).contains(&y));
+        if !inside || cells[0] == cells[1] {
+            return false;
+        }
+
+        if cells.iter().any(|&(x, y)| self.p_board.get(x, y).is_some()) {
+            return false;
+        }
+
+        let mut stones = self.superpositions.clone();
+        stones.push(Superposition {
+            cells,
+            color: Color::Black(PColor::Strong),
+        });
+        superposition::is_consistent(&stones)
+    }
+
+    pub fn place_superposed(&mut self, cells: [(usize, usize); 2]) -> Result<Color, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.rules.allow_superposition {
+            return Err(GameError::SuperpositionNotAllowed);
+        }
+
+        if !self.can_superpose(cells) {
+            return Err(GameError::InvalidSuperposition(cells));
+        }
+
+        let stone = self.update_player(self.turn, Player::consume_stone);
+        let color = match self.turn {
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
+        };
+        let superposition = Superposition { cells, color };
+
+        for (x, y) in cells {
+            self.superposed.set(x, y);
+        }
+        self.model.superpose(color);
+        self.hash ^= zobrist::superposition_key(&superposition)
+            ^ self.model.superposed_key(self.superpositions.len());
+
+        self.superpositions.push(superposition);
+        self.history.push(Move::PlaceSuperposed(superposition));
+
+        self.pass_turn(&[]);
+
+        Ok(color)
+    }
+
+    pub fn place_influencer(&mut self, x: usize, y: usize) -> Result<Color, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if self.rules.influence.is_none() {
+            return Err(GameError::InfluenceNotAllowed);
+        }
+
+        if !self.is_valid_position(x, y) {
+            return Err(GameError::InvalidPosition(None));
+        }
+
+        let stone = self.update_player(self.turn, Player::consume_stone);
+        let color = match self.turn {
+            PlayerKind::Black => Color::Black(stone),
+            PlayerKind::White => Color::White(stone),
+        };
+
+        self.p_board.set(x, y, Some(color));
+        self.model.place(x, y, color);
+        self.influencers.set(x, y);
+        self.hash ^= zobrist::stone_key(x, y, color) ^ self.model.key(x, y);
+        self.hash ^= zobrist::influencer_key(x, y);
+        self.history.push(Move::PlaceInfluencer { x, y, color });
+
+        self.pass_turn(&[(x, y)]);
+
+        Ok(color)
+    }
+
+    pub fn can_flip(&self, x: usize, y: usize) -> bool {
+        if x >= BOARD_SIZE || y >= BOARD_SIZE {
+            return false;
+        }
+
+        // A certain stone always observes as its color, so no gate turns it.
+        match self.stone(x, y) {
+            None | Some(Color::Black(PColor::Certain) | Color::White(PColor::Certain)) => {
+                return false
             }
+            Some(_) => (),
         }
-        println!("{} stones placed", debug_count);
 
-        self.take_turn();
+        // Nor does one a persistent collapse has settled.
+        if self
+            .black_probability(x, y)
+            .is_some_and(|p| p <= 0.0 || p >= 1.0)
+        {
+            return false;
+        }
+
+        self.rules.geometry().neighbours(x, y).any(|(nx, ny)| {
+            self.stone(nx, ny)
+                .is_some_and(|color| color.as_player_kind() == self.turn)
+        })
+    }
+
+    pub fn flip(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.can_flip(x, y) {
+            return Err(GameError::InvalidGateTarget(x, y));
+        }
+
+        let before = self.model.key(x, y);
+        self.model.flip(x, y)?;
+        self.hash ^= before ^ self.model.key(x, y);
+
+        self.history.push(Move::Flip { x, y });
+
+        self.pass_turn(&[]);
+
+        Ok(())
+    }
+
+    // Only an opponent's stone that is not yet certain can be erased, and only
+    // while the chance of it turning out the opponent's color is below the
+    // threshold.
+    pub fn can_erase(&self, x: usize, y: usize) -> bool {
+        let Some(threshold) = self.rules.erasure.filter(|_| !self.rules.gravity) else {
+            return false;
+        };
+        if x >= BOARD_SIZE || y >= BOARD_SIZE || !self.uncertain_stones().get(x, y) {
+            return false;
+        }
+
+        let opponent = self.turn.opponent();
+        let Some(black) = self.black_probability(x, y) else {
+            return false;
+        };
+        let p = match opponent {
+            PlayerKind::Black => black,
+            PlayerKind::White => 1.0 - black,
+        };
+
+        self.stone(x, y)
+            .is_some_and(|color| color.as_player_kind() == opponent)
+            && p * 100.0 < threshold as f64
+    }
+
+    pub fn erase(&mut self, x: usize, y: usize) -> Result<Color, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if self.rules.erasure.is_none() || self.rules.gravity {
+            return Err(GameError::ErasureNotAllowed);
+        }
+
+        if !self.can_erase(x, y) {
+            return Err(GameError::InvalidErasureTarget(x, y));
+        }
+
+        let color = self
+            .p_board
+            .get(x, y)
+            .expect("an erasable cell holds a stone");
+        self.hash ^= zobrist::stone_key(x, y, color) ^ self.model.key(x, y);
+        self.p_board.set(x, y, None);
+        self.model.remove(x, y);
+
+        if self.influencers.get(x, y) {
+            self.influencers.clear(x, y);
+            self.hash ^= zobrist::influencer_key(x, y);
+        }
+
+        // Under regional observation the erased stone no longer counts
+        // towards a five where it was last seen.
+        if self.rules.regional_observation {
+            if let Some(seen) = self.lines.board().get(x, y) {
+                self.hash ^= zobrist::observed_cell_key(x, y, seen);
+                self.lines.set(x, y, None);
+            }
+        }
+
+        // The partner of an erased entangled stone carries on as a stone of
+        // its own.
+        let mut entanglements = std::mem::take(&mut self.entanglements);
+        entanglements.retain(|entanglement| {
+            let erased = entanglement.first == (x, y) || entanglement.second == (x, y);
+            if erased {
+                self.hash ^= zobrist::entanglement_key(entanglement);
+            }
+            !erased
+        });
+        self.entanglements = entanglements;
+
+        self.history.push(Move::Erase { x, y, color });
+
+        self.pass_turn(&[]);
 
         Ok(color)
     }
 
     pub fn get_winners(&mut self) -> HashSet<PlayerKind> {
-        const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+        self.lines.winners()
+    }
+
+    pub fn observe(&mut self) -> ObservedBoard {
+        self.observe_with(&mut thread_rng())
+    }
+
+    pub fn try_observe(&mut self) -> Result<ObservedBoard, GameError> {
+        self.try_observe_with(&mut thread_rng())
+    }
+
+    pub fn try_observe_with<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<ObservedBoard, GameError> {
+        self.check_observation(None)?;
+
+        Ok(self.observe_with(rng))
+    }
+
+    // Whether the player to move may observe `region` (or the whole board)
+    // under these rules.
+    pub fn check_observation(&self, region: Option<Region>) -> Result<(), GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        match region {
+            None if self.rules.regional_observation => return Err(GameError::RegionRequired),
+            Some(_) if !self.rules.regional_observation => {
+                return Err(GameError::RegionalObservationNotAllowed)
+            }
+            Some(region) if !region.is_valid(self.rules.grid) => {
+                return Err(GameError::InvalidRegion(region))
+            }
+            _ => {}
+        }
+
+        if !self.player(self.turn).can_observe() {
+            return Err(GameError::NoObservationsLeft);
+        }
+
+        Ok(())
+    }
+
+    pub fn observe_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> ObservedBoard {
+        let observed = self.sample_bits(rng);
+        self.apply_observed_bits(observed, None)
+            .expect("a sampled board settles every superposed stone");
+
+        observed.to_array()
+    }
+
+    pub fn try_observe_region(&mut self, region: Region) -> Result<ObservedBoard, GameError> {
+        self.try_observe_region_with(region, &mut thread_rng())
+    }
+
+    pub fn try_observe_region_with<R: Rng + ?Sized>(
+        &mut self,
+        region: Region,
+        rng: &mut R,
+    ) -> Result<ObservedBoard, GameError> {
+        self.check_observation(Some(region))?;
+
+        Ok(self.observe_region_with(region, rng))
+    }
+
+    pub fn observe_region_with<R: Rng + ?Sized>(
+        &mut self,
+        region: Region,
+        rng: &mut R,
+    ) -> ObservedBoard {
+        let observed = self.sample_region_bits(region, rng);
+        self.apply_observed_bits(observed, Some(region))
+            .expect("a region never settles superposed stones");
+
+        observed.to_array()
+    }
+
+    // The board as it would look after observing only `region`: cells outside
+    // it keep whatever was seen last time.
+    pub fn sample_region_bits<R: Rng + ?Sized>(&self, region: Region, rng: &mut R) -> ObservedBits {
+        let sample = self.sample_bits(rng);
+        let previous = self.lines.board();
+        let mask = region.mask(self.rules.grid);
+
+        ObservedBits {
+            stones: (previous.stones & !mask) | (sample.stones & mask),
+            black: (previous.black & !mask) | (sample.black & mask),
+        }
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ObservedBoard {
+        self.sample_bits(rng).to_array()
+    }
 
-        let mut winners = HashSet::new();
+    pub fn sample_bits<R: Rng + ?Sized>(&self, rng: &mut R) -> ObservedBits {
+        let mut observed = ObservedBits {
+            stones: self.p_board.occupied(),
+            black: Bitboard::EMPTY,
+        };
 
-        for y in 0..BOARD_SIZE {
-            for x in 0..BOARD_SIZE {
-                if self.observed_board[y][x].is_none() {
+        observed.black = self.model.sample(&self.p_board, &mut || rng.gen::<f64>());
+
+        // Influencers collapse first. Every other stone next to one is then
+        // drawn again, pulled `influence` percentage points towards the color
+        // of each influencing neighbour.
+        if let Some(influence) = self.rules.influence {
+            for (x, y) in (self.uncertain_stones() & !self.influencers).iter() {
+                let pull: i32 = self
+                    .rules
+                    .geometry()
+                    .neighbours(x, y)
+                    .filter(|&(nx, ny)| self.influencers.get(nx, ny))
+                    .map(|(nx, ny)| if observed.black.get(nx, ny) { 1 } else { -1 })
+                    .sum();
+                if pull == 0 {
                     continue;
                 }
-                for d in DIRECTIONS {
-                    if self.check_win_dfs(x, y, d, 1) {
-                        winners.insert(self.observed_board[y][x].unwrap());
-                    }
+
+                let p = self.black_probability(x, y).unwrap_or_default()
+                    + (pull * i32::from(influence)) as f64 / 100.0;
+                if rng.gen::<f64>() < p {
+                    observed.black.set(x, y);
+                } else {
+                    observed.black.clear(x, y);
                 }
             }
         }
 
-        winners
+        // The pair is drawn from its joint distribution: the color the first
+        // stone was seen as picks a row, which weighs a single color for the
+        // second stone.
+        for entanglement in &self.entanglements {
+            let (x1, y1) = entanglement.first;
+            let (x2, y2) = entanglement.second;
+            let p = self.black_probability(x1, y1).unwrap_or_default();
+            let row = entanglement.joint_distribution(p)[usize::from(observed.black.get(x1, y1))];
+            let second = if row[1] > row[0] {
+                PlayerKind::Black
+            } else {
+                PlayerKind::White
+            };
+            observed.set(x2, y2, Some(second));
+        }
+
+        let cells = superposition::collapse(&self.superpositions, rng);
+        for (index, (x, y)) in cells.into_iter().enumerate() {
+            let kind = if rng.gen::<f64>() < self.model.superposed_black_probability(index) {
+                PlayerKind::Black
+            } else {
+                PlayerKind::White
+            };
+            observed.set(x, y, Some(kind));
+        }
+
+        observed
+    }
+
+    pub fn apply_observation(&mut self, observed_board: ObservedBoard) -> Result<(), GameError> {
+        self.check_observation(None)?;
+        self.apply_observed_bits(ObservedBits::from(&observed_board), None)
     }
 
-    fn check_win_dfs(&self, x: usize, y: usize, direction: (isize, isize), count: usize) -> bool {
-        if count >= 5 {
-            return true;
+    pub fn apply_region_observation(
+        &mut self,
+        region: Region,
+        observed_board: ObservedBoard,
+    ) -> Result<(), GameError> {
+        self.check_observation(Some(region))?;
+        self.apply_observed_bits(ObservedBits::from(&observed_board), Some(region))
+    }
+
+    // Settling comes first, so that a board the superposed stones cannot be
+    // settled on leaves the game untouched.
+    fn apply_observed_bits(
+        &mut self,
+        observed: ObservedBits,
+        region: Option<Region>,
+    ) -> Result<(), GameError> {
+        if self.rules.persistent_collapse {
+            let mask = region.map_or(Bitboard::FULL, |region| region.mask(self.rules.grid));
+            self.settle(&observed, mask, region.is_none())?;
         }
 
-        let new_x = x as isize + direction.0;
-        let new_y = y as isize + direction.1;
+        let who_observed = self.turn;
+        self.update_player(who_observed, Player::consume_observation);
 
-        if new_x < 0 || new_x >= BOARD_SIZE as isize || new_y < 0 || new_y >= BOARD_SIZE as isize {
-            return false;
+        if self.rules.observe_passes_turn {
+            self.take_turn();
         }
 
-        let new_x = new_x as usize;
-        let new_y = new_y as usize;
+        if self.rules.regional_observation {
+            self.hash ^=
+                zobrist::observed_key(self.lines.board()) ^ zobrist::observed_key(&observed);
+        }
 
-        if self.observed_board[new_y][new_x] == self.observed_board[y][x] {
-            self.check_win_dfs(new_x, new_y, direction, count + 1)
-        } else {
-            false
+        self.lines.update(&observed);
+        let board = Box::new(observed.to_array());
+        self.history.push(match region {
+            Some(region) => Move::ObserveRegion { region, board },
+            None => Move::Observe(board),
+        });
+
+        self.winner = decide_winner(&self.lines.winners(), who_observed, self.rules.tie_break);
+
+        Ok(())
+    }
+
+    // Under persistent collapse an observed stone keeps the color it was seen
+    // as, at 100% or 0% from then on. Superposed stones only settle on a full
+    // observation, since a region cannot tell which of two overlapping stones
+    // it saw, and become plain stones where they were seen.
+    fn settle(
+        &mut self,
+        observed: &ObservedBits,
+        mask: Bitboard,
+        full: bool,
+    ) -> Result<(), GameError> {
+        if full && !self.superpositions.is_empty() {
+            let cells = superposition::arrangement_within(&self.superpositions, |(x, y)| {
+                observed.stones.get(x, y) && self.p_board.get(x, y).is_none()
+            })
+            .ok_or(GameError::InvalidObservation)?;
+
+            self.hash ^= self.superposed_model_key();
+            self.model.settle(&cells);
+
+            let superpositions = std::mem::take(&mut self.superpositions);
+            for (stone, (x, y)) in superpositions.iter().zip(cells) {
+                self.p_board.set(x, y, Some(stone.color));
+                self.hash ^= zobrist::superposition_key(stone)
+                    ^ zobrist::stone_key(x, y, stone.color)
+                    ^ self.model.key(x, y);
+            }
+            self.superposed = Bitboard::EMPTY;
         }
+
+        let stones = self.p_board.occupied();
+        self.hash ^= self.model_key(stones);
+        self.model
+            .collapse(stones & observed.stones & mask, observed.black);
+        self.hash ^= self.model_key(stones);
+
+        Ok(())
     }
 
-    pub fn observe(&mut self) -> ObservedBoard {
-        let mut observed_board = [[None; BOARD_SIZE]; BOARD_SIZE];
+    fn superposed_model_key(&self) -> u64 {
+        (0..self.superpositions.len())
+            .map(|index| self.model.superposed_key(index))
+            .fold(0, |hash, key| hash ^ key)
+    }
 
-        #[warn(clippy::needless_range_loop)]
-        for y in 0..BOARD_SIZE {
-            for x in 0..BOARD_SIZE {
-                observed_board[y][x] = self.p_board[y][x].map(|color| {
-                    let random_number: usize = (random::<f64>() * 100.0).floor() as usize;
-                    let p: u8 = color.into();
+    fn model_key(&self, stones: Bitboard) -> u64 {
+        stones
+            .iter()
+            .fold(0, |hash, (x, y)| hash ^ self.model.key(x, y))
+    }
+}
+
+pub const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+pub fn winners(board: &ObservedBoard) -> HashSet<PlayerKind> {
+    ObservedBits::from(board).winners()
+}
+
+pub fn winners_on(board: &ObservedBoard, geometry: Geometry) -> HashSet<PlayerKind> {
+    ObservedBits::from(board).winners_on(geometry)
+}
 
-                    if random_number < p.into() {
-                        PlayerKind::Black
-                    } else {
-                        PlayerKind::White
-                    }
-                });
+/// The original cell-by-cell search, kept as the reference that the bitboard
+/// five detection is checked and benchmarked against.
+pub fn scan_winners(board: &ObservedBoard) -> HashSet<PlayerKind> {
+    scan_winners_on(board, Geometry::default())
+}
+
+pub fn scan_winners_on(board: &ObservedBoard, geometry: Geometry) -> HashSet<PlayerKind> {
+    let mut winners = HashSet::new();
+
+    for y in 0..BOARD_SIZE {
+        for x in 0..BOARD_SIZE {
+            if board[y][x].is_none() {
+                continue;
+            }
+            for &d in geometry.directions() {
+                if check_win_dfs(board, x, y, d, 1, geometry) {
+                    winners.insert(board[y][x].unwrap());
+                }
             }
         }
+    }
 
-        self.take_turn();
+    winners
+}
+
+fn check_win_dfs(
+    board: &ObservedBoard,
+    x: usize,
+    y: usize,
+    direction: (isize, isize),
+    count: usize,
+    geometry: Geometry,
+) -> bool {
+    if count >= 5 {
+        return true;
+    }
+
+    let Some((new_x, new_y)) = geometry.step(x, y, direction, 1) else {
+        return false;
+    };
+
+    if board[new_y][new_x] == board[y][x] {
+        check_win_dfs(board, new_x, new_y, direction, count + 1, geometry)
+    } else {
+        false
+    }
+}
+
+// The two-player game is seats 0 and 1 of `decide_seat_winner`, with black
+// first.
+pub fn decide_winner(
+    winners: &HashSet<PlayerKind>,
+    who_observed: PlayerKind,
+    tie_break: TieBreak,
+) -> Option<PlayerKind> {
+    let kinds = [PlayerKind::Black, PlayerKind::White];
+    let seat = |kind| usize::from(kind == PlayerKind::White);
+
+    let mut seats = [0; 2];
+    let mut count = 0;
+    for kind in kinds {
+        if winners.contains(&kind) {
+            seats[count] = seat(kind);
+            count += 1;
+        }
+    }
+
+    decide_seat_winner(&seats[..count], seat(who_observed), tie_break, kinds.len())
+        .map(|seat| kinds[seat])
+}
 
-        self.observed_board = observed_board;
+// Seats are numbered in turn order. With more than one five on the board,
+// `Observer` prefers the observer and otherwise, like `Opponent`, the first
+// winner to play after the observer.
+pub fn decide_seat_winner(
+    winners: &[usize],
+    who_observed: usize,
+    tie_break: TieBreak,
+    players: usize,
+) -> Option<usize> {
+    let after_observer = || {
+        (1..players)
+            .map(|i| (who_observed + i) % players)
+            .find(|seat| winners.contains(seat))
+    };
 
-        observed_board
+    match (winners.len(), tie_break) {
+        (0, _) => None,
+        (1, _) => Some(winners[0]),
+        (_, TieBreak::Observer) if winners.contains(&who_observed) => Some(who_observed),
+        (_, TieBreak::Observer | TieBreak::Opponent) => after_observer(),
+        (_, TieBreak::Continue) => None,
     }
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
 pub enum GameError {
     GameIsAlreadyOver,
     InvalidPosition(Option<Color>),
+    NoObservationsLeft,
+    EntanglementNotAllowed,
+    SuperpositionNotAllowed,
+    InvalidSuperposition([(usize, usize); 2]),
+    GatesNotSupported,
+    InvalidGateTarget(usize, usize),
+    RegionRequired,
+    RegionalObservationNotAllowed,
+    InvalidRegion(Region),
+    InfluenceNotAllowed,
+    NoCertainStonesLeft,
+    ErasureNotAllowed,
+    InvalidErasureTarget(usize, usize),
+    InvalidPlayerCount(usize),
+    InvalidLattice(Lattice),
+    GravityNotEnabled,
+    ColumnFull(usize),
+    InvalidNextStone(PlayerKind),
+    CertainFive(PlayerKind),
+    UnsupportedStone(usize, usize),
+    ObservationNotAnAnswer,
+    InvalidObservation,
+    IncompatibleRules(&'static str, &'static str),
 }
 
 impl Display for GameError {
@@ -196,6 +1452,455 @@ impl Display for GameError {
                 Some(color) => write!(f, "Invalid position: {}", color),
                 None => write!(f, "Invalid position: None"),
             },
+            GameError::NoObservationsLeft => write!(f, "No observations left"),
+            GameError::EntanglementNotAllowed => write!(f, "Entanglement is not allowed"),
+            GameError::SuperpositionNotAllowed => write!(f, "Superposition is not allowed"),
+            GameError::InvalidSuperposition([(x1, y1), (x2, y2)]) => write!(
+                f,
+                "Invalid superposition: ({}, {}) and ({}, {}) cannot both hold the stone",
+                x1, y1, x2, y2
+            ),
+            GameError::GatesNotSupported => {
+                write!(f, "Gates are not supported by this stone model")
+            }
+            GameError::InvalidGateTarget(x, y) => write!(
+                f,
+                "Invalid gate target: ({}, {}) is not a stone next to one of your own",
+                x, y
+            ),
+            GameError::RegionRequired => write!(f, "Observation needs a region"),
+            GameError::RegionalObservationNotAllowed => {
+                write!(f, "Regional observation is not allowed")
+            }
+            GameError::InvalidRegion(region) => write!(f, "Invalid region: {:?}", region),
+            GameError::InfluenceNotAllowed => write!(f, "Influencing stones are not allowed"),
+            GameError::NoCertainStonesLeft => write!(f, "No certain stones left"),
+            GameError::ErasureNotAllowed => write!(f, "Erasure is not allowed"),
+            GameError::InvalidErasureTarget(x, y) => write!(
+                f,
+                "Invalid erasure target: ({}, {}) is not an uncertain opponent stone below the threshold",
+                x, y
+            ),
+            GameError::InvalidPlayerCount(players) => {
+                write!(f, "Invalid player count: {}", players)
+            }
+            GameError::InvalidLattice(lattice) => write!(
+                f,
+                "Invalid board: {}x{}x{}",
+                lattice.size, lattice.size, lattice.layers
+            ),
+            GameError::GravityNotEnabled => write!(f, "Gravity is not enabled"),
+            GameError::ColumnFull(column) => write!(f, "Column {} is full", column),
+            GameError::InvalidNextStone(kind) => {
+                write!(f, "Invalid next stone for {}: it must be strong or weak", kind)
+            }
+            GameError::CertainFive(kind) => {
+                write!(f, "Invalid position: {} already has five certain stones", kind)
+            }
+            GameError::UnsupportedStone(x, y) => write!(
+                f,
+                "Invalid position: ({}, {}) has an empty cell below it",
+                x, y
+            ),
+            GameError::ObservationNotAnAnswer => {
+                write!(f, "Puzzles are answered with a move, not an observation")
+            }
+            GameError::InvalidObservation => {
+                write!(f, "Observation does not match the stones on the board")
+            }
+            GameError::IncompatibleRules(first, second) => {
+                write!(f, "Invalid rules: {} cannot be combined with {}", first, second)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn settling_an_impossible_board_leaves_the_game_untouched() {
+        let mut game = Game::with_rules(Rules {
+            allow_superposition: true,
+            persistent_collapse: true,
+            ..Rules::default()
+        });
+        game.place_superposed([(0, 0), (1, 0)]).unwrap();
+        game.place_superposed([(0, 1), (1, 1)]).unwrap();
+        let hash = game.hash();
+
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        board[0][0] = Some(PlayerKind::Black);
+        board[0][1] = Some(PlayerKind::White);
+        assert!(matches!(
+            game.apply_observation(board),
+            Err(GameError::InvalidObservation)
+        ));
+        assert_eq!(game.hash(), hash);
+        assert_eq!(game.history().len(), 2);
+    }
+
+    #[test]
+    fn erasing_clears_the_last_seen_stone_under_regional_observation() {
+        let mut game = Game::with_rules(Rules {
+            regional_observation: true,
+            erasure: Some(95),
+            ..Rules::default()
+        });
+        game.place_stone_prohibitly(0, 0).unwrap();
+        game.place_stone_prohibitly(10, 10).unwrap();
+        game.try_observe_region(Region::Window(0, 0)).unwrap();
+        assert!(game.observed().get(0, 0).is_some());
+
+        game.erase(0, 0).unwrap();
+        assert_eq!(game.observed().get(0, 0), None);
+        assert_eq!(game.hash(), zobrist::hash(&game));
+    }
+
+    #[test]
+    fn erasure_is_turned_down_under_gravity() {
+        let rules = Rules {
+            gravity: true,
+            erasure: Some(95),
+            ..Rules::default()
+        };
+        assert!(rules.validate().is_err());
+
+        let mut game = Game::with_rules(rules);
+        game.drop_stone(0).unwrap();
+        game.drop_stone(0).unwrap();
+        game.drop_stone(0).unwrap();
+        assert!(!game.can_erase(0, BOARD_SIZE - 1));
+        assert!(game.erase(0, BOARD_SIZE - 1).is_err());
+        assert!(Position::from_game(&game).validate(game.rules()).is_err());
+    }
+
+    #[test]
+    fn handicap_is_turned_down_under_gravity() {
+        let rules = Rules {
+            gravity: true,
+            handicap: Some(Handicap {
+                player: PlayerKind::Black,
+                stones: 2,
+                stone: PColor::Certain,
+            }),
+            ..Rules::default()
+        };
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    fn certain_stones_cannot_be_flipped() {
+        let mut game = Game::with_rules(Rules {
+            stone_model: StoneModelKind::Amplitude,
+            certain_stones: 1,
+            ..Rules::default()
+        });
+        game.place_certain(0, 0).unwrap();
+        game.place_stone_prohibitly(1, 0).unwrap();
+        game.place_stone_prohibitly(5, 5).unwrap();
+
+        assert!(!game.can_flip(0, 0));
+        assert!(game.flip(0, 0).is_err());
+        assert_eq!(game.black_probability(0, 0), Some(1.0));
+    }
+
+    #[test]
+    fn settled_stones_cannot_be_flipped() {
+        let mut game = Game::with_rules(Rules {
+            stone_model: StoneModelKind::Amplitude,
+            persistent_collapse: true,
+            ..Rules::default()
+        });
+        game.place_stone_prohibitly(0, 0).unwrap();
+        game.place_stone_prohibitly(1, 0).unwrap();
+        assert!(game.can_flip(1, 0));
+
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        board[0][0] = Some(PlayerKind::Black);
+        board[0][1] = Some(PlayerKind::White);
+        game.apply_observation(board).unwrap();
+
+        // Black observed, so white is to move, next to the settled black stone.
+        assert_eq!(game.turn(), PlayerKind::White);
+        assert!(!game.can_flip(0, 0));
+        assert!(game.flip(0, 0).is_err());
+        assert_eq!(game.black_probability(0, 0), Some(1.0));
+    }
+
+    #[test]
+    fn entangled_pairs_always_sample_consistently() {
+        let mut game = Game::with_rules(Rules {
+            allow_entanglement: true,
+            ..Rules::default()
+        });
+        let correlated = Entanglement {
+            first: (0, 0),
+            second: (5, 5),
+            correlation: Correlation::Correlated,
+        };
+        let anticorrelated = Entanglement {
+            first: (10, 0),
+            second: (15, 5),
+            correlation: Correlation::Anticorrelated,
+        };
+        game.place_entangled(correlated).unwrap();
+        game.place_entangled(anticorrelated).unwrap();
+
+        for distribution in [
+            correlated.joint_distribution(0.7),
+            anticorrelated.joint_distribution(0.7),
+        ] {
+            let total: f64 = distribution.iter().flatten().sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            assert!((distribution[1][0] + distribution[1][1] - 0.7).abs() < 1e-9);
+        }
+
+        let mut firsts = HashSet::new();
+        for seed in 0..500 {
+            let observed = game.sample_bits(&mut StdRng::seed_from_u64(seed));
+            assert_eq!(observed.get(0, 0), observed.get(5, 5));
+            assert_eq!(
+                observed.get(10, 0).map(|kind| kind.opponent()),
+                observed.get(15, 5)
+            );
+            firsts.insert(observed.get(0, 0));
+        }
+        assert_eq!(firsts.len(), 2);
+    }
+
+    #[test]
+    fn drift_moves_by_the_step_and_stops_at_its_target() {
+        let even = Decoherence {
+            toward: Drift::Even,
+            step: 10,
+        };
+        assert_eq!(even.drift(90), 80);
+        assert_eq!(even.drift(30), 40);
+        assert_eq!(even.drift(55), 50);
+        assert_eq!(even.drift(45), 50);
+        assert_eq!(even.drift(50), 50);
+
+        let certain = Decoherence {
+            toward: Drift::Certain,
+            step: 10,
+        };
+        assert_eq!(certain.drift(70), 80);
+        assert_eq!(certain.drift(30), 20);
+        assert_eq!(certain.drift(95), 100);
+        assert_eq!(certain.drift(5), 0);
+        assert_eq!(certain.drift(50), 50);
+    }
+
+    #[test]
+    fn stones_drift_only_on_later_turns_without_an_observation() {
+        let mut game = Game::with_rules(Rules {
+            decoherence: Some(Decoherence {
+                toward: Drift::Even,
+                step: 10,
+            }),
+            ..Rules::default()
+        });
+        let p = |game: &Game| game.black_probability(0, 0).unwrap();
+
+        game.place_stone_prohibitly(0, 0).unwrap();
+        assert!((p(&game) - 0.7).abs() < 1e-9);
+        game.place_stone_prohibitly(1, 0).unwrap();
+        assert!((p(&game) - 0.6).abs() < 1e-9);
+        assert!((game.black_probability(1, 0).unwrap() - 0.1).abs() < 1e-9);
+
+        game.observe_with(&mut StdRng::seed_from_u64(0));
+        assert!((p(&game) - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn superposed_stones_drift_and_sample_through_the_model() {
+        for stone_model in [StoneModelKind::Classical, StoneModelKind::Amplitude] {
+            let decoherence = Decoherence {
+                toward: Drift::Certain,
+                step: 30,
+            };
+            let mut game = Game::with_rules(Rules {
+                allow_superposition: true,
+                stone_model,
+                decoherence: Some(decoherence),
+                persistent_collapse: true,
+                ..Rules::default()
+            });
+            let p = u8::from(game.place_superposed([(0, 0), (1, 0)]).unwrap());
+            assert!((game.superposed_black_probability(0) - p as f64 / 100.0).abs() < 1e-9);
+
+            game.place_stone_prohibitly(5, 5).unwrap();
+            assert_eq!(decoherence.drift(p), 100);
+            assert!((game.superposed_black_probability(0) - 1.0).abs() < 1e-9);
+            assert_eq!(game.hash(), zobrist::hash(&game));
+
+            for seed in 0..100 {
+                let observed = game.sample_bits(&mut StdRng::seed_from_u64(seed));
+                let seen = [observed.get(0, 0), observed.get(1, 0)];
+                assert!(seen.contains(&Some(PlayerKind::Black)), "{:?}", seen);
+                assert!(!seen.contains(&Some(PlayerKind::White)), "{:?}", seen);
+            }
+
+            // The drifted state is what the stone keeps once it settles.
+            game.observe_with(&mut StdRng::seed_from_u64(0));
+            let (x, y) = if game.stones().get(0, 0).is_some() {
+                (0, 0)
+            } else {
+                (1, 0)
+            };
+            assert_eq!(game.black_probability(x, y), Some(1.0));
+            assert_eq!(game.hash(), zobrist::hash(&game));
         }
     }
+
+    #[test]
+    fn influencers_pull_their_neighbours_only() {
+        for grid in [Grid::Square, Grid::Hex] {
+            let mut game = Game::with_rules(Rules {
+                influence: Some(100),
+                grid,
+                ..Rules::default()
+            });
+            game.place_influencer(5, 5).unwrap();
+            game.place_influencer(5, 4).unwrap();
+            game.place_stone_prohibitly(5, 6).unwrap();
+            game.place_stone_prohibitly(15, 15).unwrap();
+            // Diagonally next to the influencer on the square grid, but not
+            // on the hex grid.
+            game.place_stone_prohibitly(6, 6).unwrap();
+
+            let mut diagonal_differs = false;
+            let mut influencers_differ = false;
+            for seed in 0..300 {
+                let observed = game.sample_bits(&mut StdRng::seed_from_u64(seed));
+                let influencer = observed.get(5, 5);
+                assert_eq!(observed.get(5, 6), influencer, "{:?}", grid);
+                diagonal_differs |= observed.get(6, 6) != influencer;
+                influencers_differ |= observed.get(5, 4) != influencer;
+            }
+
+            assert_eq!(diagonal_differs, grid == Grid::Hex);
+            assert!(influencers_differ, "{:?}", grid);
+        }
+    }
+
+    #[test]
+    fn team_turns_rotate_through_all_four_players() {
+        let mut game = Game::with_rules(Rules {
+            teams: true,
+            observe_passes_turn: false,
+            ..Rules::default()
+        });
+        let mover = |game: &Game| (game.turn(), game.player(game.turn()).member());
+
+        let order = [
+            (PlayerKind::Black, 0),
+            (PlayerKind::White, 0),
+            (PlayerKind::Black, 1),
+            (PlayerKind::White, 1),
+            (PlayerKind::Black, 0),
+        ];
+        for (i, &expected) in order.iter().enumerate() {
+            assert_eq!(mover(&game), expected);
+            if i + 1 < order.len() {
+                game.place_stone_prohibitly(i, 0).unwrap();
+            }
+        }
+
+        // An observation that keeps the turn keeps the same player too.
+        game.observe_with(&mut StdRng::seed_from_u64(0));
+        assert_eq!(mover(&game), (PlayerKind::Black, 0));
+        assert_eq!(game.teammate(PlayerKind::Black).unwrap().member(), 1);
+        game.place_stone_prohibitly(10, 10).unwrap();
+        assert_eq!(mover(&game), (PlayerKind::White, 0));
+    }
+
+    #[test]
+    fn stones_drop_to_the_lowest_free_cell_of_their_column() {
+        let mut game = Game::with_rules(Rules {
+            gravity: true,
+            ..Rules::default()
+        });
+        let bottom = BOARD_SIZE - 1;
+
+        assert_eq!(game.drop_position(3), Some((3, bottom)));
+        assert!(!game.is_valid_position(3, 0));
+        assert!(game.place_stone_prohibitly(3, 0).is_err());
+
+        let (x, y, _) = game.drop_stone(3).unwrap();
+        assert_eq!((x, y), (3, bottom));
+        assert_eq!(game.drop_position(3), Some((3, bottom - 1)));
+        assert!(game.is_valid_position(3, bottom - 1));
+        assert!(game.place_stone_prohibitly(3, bottom - 1).is_ok());
+        assert_eq!(game.drop_position(BOARD_SIZE), None);
+    }
+
+    #[test]
+    fn a_full_column_takes_no_more_stones() {
+        let mut game = Game::with_rules(Rules {
+            gravity: true,
+            ..Rules::default()
+        });
+        for _ in 0..BOARD_SIZE {
+            game.drop_stone(0).unwrap();
+        }
+
+        assert_eq!(game.drop_position(0), None);
+        assert!(matches!(game.drop_stone(0), Err(GameError::ColumnFull(0))));
+        assert!((0..BOARD_SIZE).all(|y| !game.is_valid_position(0, y)));
+    }
+
+    #[test]
+    fn hex_regions_follow_the_hex_lines() {
+        let mut game = Game::with_rules(Rules {
+            regional_observation: true,
+            grid: Grid::Hex,
+            ..Rules::default()
+        });
+        game.place_stone_prohibitly(7, 7).unwrap();
+
+        assert!(matches!(
+            game.try_observe_region(Region::Diagonal(7, 7)),
+            Err(GameError::InvalidRegion(Region::Diagonal(7, 7)))
+        ));
+
+        // The six neighbours and the twelve cells two steps away.
+        let window = Region::Window(7, 7).mask(Grid::Hex);
+        assert_eq!(window.iter().count(), 19);
+        assert!(window.get(9, 5) && window.get(5, 9));
+        assert!(!window.get(9, 9) && !window.get(5, 5));
+        assert_eq!(Region::Window(7, 7).mask(Grid::Square).iter().count(), 25);
+
+        game.try_observe_region(Region::AntiDiagonal(7, 7)).unwrap();
+    }
+
+    #[test]
+    fn gravity_is_turned_down_on_the_hex_grid() {
+        let rules = Rules {
+            gravity: true,
+            grid: Grid::Hex,
+            ..Rules::default()
+        };
+        assert!(matches!(
+            rules.validate(),
+            Err(GameError::IncompatibleRules("gravity", "hex"))
+        ));
+    }
+
+    #[test]
+    fn superposition_is_turned_down_under_gravity() {
+        let rules = Rules {
+            gravity: true,
+            allow_superposition: true,
+            ..Rules::default()
+        };
+        assert!(matches!(
+            rules.validate(),
+            Err(GameError::IncompatibleRules("gravity", "superposition"))
+        ));
+    }
 }
@@ -0,0 +1,192 @@
+use crate::game::{Topology, BOARD_SIZE, DIRECTIONS};
+
+// A hex board is kept in axial coordinates on the square board, x = q and
+// y = r, which makes it a rhombus. Its three lines are the rows, the columns
+// and the anti-diagonals of the square grid.
+pub const HEX_DIRECTIONS: [(isize, isize); 3] = [(1, 0), (0, 1), (1, -1)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Grid {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl Grid {
+    pub fn directions(&self) -> &'static [(isize, isize)] {
+        match self {
+            Grid::Square => &DIRECTIONS,
+            Grid::Hex => &HEX_DIRECTIONS,
+        }
+    }
+
+    // Where to draw a cell, in units of the distance between neighbouring
+    // cells. Hex cells are pointy-topped, each row shifted half a cell.
+    pub fn center(&self, x: usize, y: usize) -> (f64, f64) {
+        match self {
+            Grid::Square => (x as f64, y as f64),
+            Grid::Hex => (x as f64 + y as f64 / 2.0, y as f64 * 3f64.sqrt() / 2.0),
+        }
+    }
+
+    // Hex cells are written in cube coordinates `q,r,s` with q + r + s = 0.
+    pub fn notation(&self, x: usize, y: usize) -> String {
+        match self {
+            Grid::Square => format!("{},{}", x, y),
+            Grid::Hex => format!("{},{},{}", x, y, -(x as isize) - y as isize),
+        }
+    }
+
+    pub fn parse_notation(&self, text: &str) -> Option<(usize, usize)> {
+        let parts: Vec<isize> = text
+            .split(',')
+            .map(|part| part.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        let (x, y) = match (self, parts.as_slice()) {
+            (Grid::Square, &[x, y]) => (x, y),
+            (Grid::Hex, &[q, r, s]) if q + r + s == 0 => (q, r),
+            _ => return None,
+        };
+
+        let inside = (0..BOARD_SIZE as isize).contains(&x) && (0..BOARD_SIZE as isize).contains(&y);
+        inside.then_some((x as usize, y as usize))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Geometry {
+    pub grid: Grid,
+    pub topology: Topology,
+}
+
+impl Geometry {
+    pub fn directions(&self) -> &'static [(isize, isize)] {
+        self.grid.directions()
+    }
+
+    pub fn step(
+        &self,
+        x: usize,
+        y: usize,
+        direction: (isize, isize),
+        steps: isize,
+    ) -> Option<(usize, usize)> {
+        self.topology.step(x, y, direction, steps)
+    }
+
+    // Eight neighbours on the square grid, six on the hex grid.
+    pub fn neighbours(self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.directions()
+            .iter()
+            .flat_map(move |&direction| [1, -1].map(|steps| self.step(x, y, direction, steps)))
+            .flatten()
+    }
+}
+
+pub type Point = [usize; 3];
+pub type Direction = [isize; 3];
+
+// One direction for each pair of opposite steps of -1, 0 or 1 along the first
+// `dimensions` axes: the four lines of the flat board, thirteen in a cube.
+pub fn line_directions(dimensions: usize) -> Vec<Direction> {
+    (0..27)
+        .map(|i| [i % 3, i / 3 % 3, i / 9].map(|step| step as isize - 1))
+        .filter(|direction| direction[dimensions..].iter().all(|&step| step == 0))
+        .filter(|direction| {
+            direction
+                .iter()
+                .find(|&&step| step != 0)
+                .is_some_and(|&step| step > 0)
+        })
+        .collect()
+}
+
+// Square boards stacked into layers, for the cube mode only: a cube has as
+// many layers as rows, and at least two. `Game` is not generic over it; the
+// flat board keeps its own bitboard-backed representation and rules.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Lattice {
+    pub size: usize,
+    pub layers: usize,
+}
+
+impl Lattice {
+    pub fn cube(size: usize) -> Lattice {
+        Lattice { size, layers: size }
+    }
+
+    pub fn directions(&self) -> Vec<Direction> {
+        line_directions(3)
+    }
+
+    pub fn cells(&self) -> usize {
+        self.size * self.size * self.layers
+    }
+
+    pub fn index(&self, [x, y, z]: Point) -> usize {
+        (z * self.size + y) * self.size + x
+    }
+
+    pub fn point(&self, index: usize) -> Point {
+        [
+            index % self.size,
+            index / self.size % self.size,
+            index / (self.size * self.size),
+        ]
+    }
+
+    pub fn contains(&self, [x, y, z]: Point) -> bool {
+        x < self.size && y < self.size && z < self.layers
+    }
+
+    pub fn step(&self, point: Point, direction: Direction, steps: isize) -> Option<Point> {
+        let mut next = [0; 3];
+        for axis in 0..3 {
+            next[axis] = point[axis].checked_add_signed(direction[axis] * steps)?;
+        }
+        self.contains(next).then_some(next)
+    }
+
+    // Whether five of the marked cells, indexed by `index`, are in a line.
+    pub fn has_five(&self, marked: &[bool]) -> bool {
+        if marked.iter().filter(|&&marked| marked).count() < 5 {
+            return false;
+        }
+
+        let directions = self.directions();
+        (0..self.cells())
+            .filter(|&index| marked[index])
+            .any(|index| {
+                let point = self.point(index);
+                directions.iter().any(|&direction| {
+                    (1..5).all(|i| {
+                        self.step(point, direction, i)
+                            .is_some_and(|next| marked[self.index(next)])
+                    })
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_cube_has_thirteen_line_directions() {
+        assert_eq!(line_directions(2).len(), DIRECTIONS.len());
+        assert_eq!(line_directions(3).len(), 13);
+        assert_eq!(Lattice::cube(7).directions().len(), 13);
+    }
+
+    #[test]
+    fn indices_and_points_round_trip() {
+        let lattice = Lattice { size: 6, layers: 3 };
+        for index in 0..lattice.cells() {
+            let point = lattice.point(index);
+            assert!(lattice.contains(point));
+            assert_eq!(lattice.index(point), index);
+        }
+        assert!(!lattice.contains([0, 0, 3]));
+    }
+}
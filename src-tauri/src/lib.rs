@@ -0,0 +1,19 @@
+pub mod ai;
+pub mod analysis;
+pub mod bitboard;
+pub mod cube;
+pub mod game;
+pub mod grid;
+pub mod lines;
+pub mod multiplayer;
+pub mod player;
+pub mod position;
+pub mod protocol;
+pub mod puzzle;
+pub mod rating;
+pub mod record;
+pub mod simulation;
+pub mod stone_model;
+pub mod superposition;
+pub mod tournament;
+pub mod zobrist;
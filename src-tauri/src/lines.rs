@@ -0,0 +1,208 @@
+use std::{collections::HashSet, sync::OnceLock};
+
+use crate::{
+    bitboard::ObservedBits,
+    game::{Topology, BOARD_SIZE},
+    grid::{Geometry, Grid},
+    player::PlayerKind,
+};
+
+const WIN_LENGTH: usize = 5;
+
+struct Windows {
+    count: usize,
+    through_cell: Vec<Vec<usize>>,
+}
+
+// Every run of five cells on the board, and for each cell the runs passing
+// through it (at most five per direction, exactly five on a torus).
+fn windows(geometry: Geometry) -> &'static Windows {
+    static BOUNDED: OnceLock<Windows> = OnceLock::new();
+    static TOROIDAL: OnceLock<Windows> = OnceLock::new();
+    static HEX: OnceLock<Windows> = OnceLock::new();
+    static TOROIDAL_HEX: OnceLock<Windows> = OnceLock::new();
+
+    let windows = match (geometry.grid, geometry.topology) {
+        (Grid::Square, Topology::Bounded) => &BOUNDED,
+        (Grid::Square, Topology::Toroidal) => &TOROIDAL,
+        (Grid::Hex, Topology::Bounded) => &HEX,
+        (Grid::Hex, Topology::Toroidal) => &TOROIDAL_HEX,
+    };
+
+    windows.get_or_init(|| {
+        let mut through_cell = vec![Vec::new(); BOARD_SIZE * BOARD_SIZE];
+        let mut count = 0;
+
+        for &direction in geometry.directions() {
+            for y in 0..BOARD_SIZE {
+                for x in 0..BOARD_SIZE {
+                    let cells: Option<Vec<(usize, usize)>> = (0..WIN_LENGTH as isize)
+                        .map(|i| geometry.step(x, y, direction, i))
+                        .collect();
+                    let Some(cells) = cells else {
+                        continue;
+                    };
+
+                    for (cx, cy) in cells {
+                        through_cell[cy * BOARD_SIZE + cx].push(count);
+                    }
+                    count += 1;
+                }
+            }
+        }
+
+        Windows {
+            count,
+            through_cell,
+        }
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct LineTracker {
+    geometry: Geometry,
+    board: ObservedBits,
+    black: Vec<u8>,
+    white: Vec<u8>,
+    black_fives: usize,
+    white_fives: usize,
+}
+
+impl LineTracker {
+    pub fn new() -> LineTracker {
+        LineTracker::with_geometry(Geometry::default())
+    }
+
+    pub fn with_geometry(geometry: Geometry) -> LineTracker {
+        let count = windows(geometry).count;
+        LineTracker {
+            geometry,
+            board: ObservedBits::default(),
+            black: vec![0; count],
+            white: vec![0; count],
+            black_fives: 0,
+            white_fives: 0,
+        }
+    }
+
+    pub fn board(&self) -> &ObservedBits {
+        &self.board
+    }
+
+    pub fn has_five(&self, kind: PlayerKind) -> bool {
+        match kind {
+            PlayerKind::Black => self.black_fives > 0,
+            PlayerKind::White => self.white_fives > 0,
+        }
+    }
+
+    pub fn winners(&self) -> HashSet<PlayerKind> {
+        [PlayerKind::Black, PlayerKind::White]
+            .into_iter()
+            .filter(|&kind| self.has_five(kind))
+            .collect()
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, kind: Option<PlayerKind>) {
+        let old = self.board.get(x, y);
+        if old == kind {
+            return;
+        }
+
+        for &window in &windows(self.geometry).through_cell[y * BOARD_SIZE + x] {
+            match old {
+                Some(PlayerKind::Black) => {
+                    if self.black[window] as usize == WIN_LENGTH {
+                        self.black_fives -= 1;
+                    }
+                    self.black[window] -= 1;
+                }
+                Some(PlayerKind::White) => {
+                    if self.white[window] as usize == WIN_LENGTH {
+                        self.white_fives -= 1;
+                    }
+                    self.white[window] -= 1;
+                }
+                None => (),
+            }
+
+            match kind {
+                Some(PlayerKind::Black) => {
+                    self.black[window] += 1;
+                    if self.black[window] as usize == WIN_LENGTH {
+                        self.black_fives += 1;
+                    }
+                }
+                Some(PlayerKind::White) => {
+                    self.white[window] += 1;
+                    if self.white[window] as usize == WIN_LENGTH {
+                        self.white_fives += 1;
+                    }
+                }
+                None => (),
+            }
+        }
+
+        self.board.set(x, y, kind);
+    }
+
+    pub fn update(&mut self, observed: &ObservedBits) {
+        let changed = (self.board.stones ^ observed.stones) | (self.board.black ^ observed.black);
+        for (x, y) in changed.iter() {
+            self.set(x, y, observed.get(x, y));
+        }
+    }
+}
+
+impl Default for LineTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::game::scan_winners_on;
+
+    fn random_kind(rng: &mut StdRng) -> Option<PlayerKind> {
+        match rng.gen_range(0..3) {
+            0 => None,
+            1 => Some(PlayerKind::Black),
+            _ => Some(PlayerKind::White),
+        }
+    }
+
+    #[test]
+    fn winners_agree_with_the_cell_by_cell_scan() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            for grid in [Grid::Square, Grid::Hex] {
+                let geometry = Geometry { grid, topology };
+                let mut tracker = LineTracker::with_geometry(geometry);
+
+                // Single cells changing one by one, as erasing and regional
+                // observation do.
+                for _ in 0..2000 {
+                    let (x, y) = (rng.gen_range(0..BOARD_SIZE), rng.gen_range(0..BOARD_SIZE));
+                    tracker.set(x, y, random_kind(&mut rng));
+                    let board = tracker.board().to_array();
+                    assert_eq!(tracker.winners(), scan_winners_on(&board, geometry));
+                }
+
+                // Whole boards replacing each other, as full observations do.
+                for _ in 0..100 {
+                    let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+                    for cell in board.iter_mut().flatten() {
+                        *cell = random_kind(&mut rng);
+                    }
+                    tracker.update(&ObservedBits::from(&board));
+                    assert_eq!(tracker.board().to_array(), board);
+                    assert_eq!(tracker.winners(), scan_winners_on(&board, geometry));
+                }
+            }
+        }
+    }
+}
@@ -1,26 +1,73 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{borrow::BorrowMut, sync::Mutex};
+use std::{borrow::BorrowMut, fs, path::PathBuf, sync::Mutex};
 
-use crate::game::BOARD_SIZE;
-use game::Game;
-use player::{Color, PlayerKind};
+use quantum_gomoku::{
+    ai::Agent,
+    analysis::cube_observation_outcome,
+    cube::{CubeGame, CubeRules},
+    game::{
+        Action, Correlation, Decoherence, Drift, Entanglement, Game, Handicap, ObservedBoard,
+        Rules, Topology, BOARD_SIZE, MAX_HANDICAP,
+    },
+    grid::{Grid, Lattice},
+    multiplayer::{MultiGame, MultiRules, SeatBoard},
+    player::{Color, PColor, PlayerKind},
+    position::Position,
+    protocol::{format_region, parse_action, parse_region, ExternalEngine},
+    puzzle::{self, Goal, Progress},
+    stone_model::StoneModelKind,
+    superposition::Superposition,
+};
 
 use tauri::{LogicalSize, Manager, Runtime};
 
-mod game;
-mod player;
-
 fn main() {
     tauri::Builder::default()
+        // src/main.ts only calls init_game, get_board_size, place_stone,
+        // get_board and observe; the other commands have no screen yet and
+        // are reached through `invoke` alone.
         .invoke_handler(tauri::generate_handler![
             init_game,
             get_board_size,
+            get_board_layout,
             greet,
             get_board,
             place_stone,
-            observe
+            drop_stone,
+            place_entangled,
+            get_entanglements,
+            place_superposed,
+            get_superpositions,
+            flip,
+            erase,
+            place_influencer,
+            place_certain,
+            get_influencers,
+            observe,
+            observe_region,
+            get_observed_board,
+            begin_setup,
+            setup_cell,
+            setup_turn,
+            setup_next_stone,
+            validate_setup,
+            start_from_setup,
+            get_puzzles,
+            start_puzzle,
+            answer_puzzle,
+            init_multiplayer,
+            get_multiplayer_board,
+            place_multiplayer,
+            observe_multiplayer,
+            init_cube,
+            get_cube_board,
+            place_cube,
+            observe_cube,
+            get_cube_outcome,
+            connect_engine,
+            engine_turn
         ])
         .setup(|app| {
             let main_window = app.get_window("main").unwrap();
@@ -39,27 +86,124 @@ fn main() {
 
 struct MyState {
     game: Mutex<Game>,
+    engine: Mutex<Option<ExternalEngine>>,
+    multiplayer: Mutex<Option<MultiGame>>,
+    cube: Mutex<Option<CubeGame>>,
+    setup: Mutex<Option<Position>>,
 }
 
 impl MyState {
     fn new() -> Self {
         Self {
             game: Mutex::new(Game::new()),
+            engine: Mutex::new(None),
+            multiplayer: Mutex::new(None),
+            cube: Mutex::new(None),
+            setup: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct RulesJS {
+    entanglement: bool,
+    superposition: bool,
+    amplitude: bool,
+    regional: bool,
+    persistent: bool,
+    decoherence: Option<DecoherenceJS>,
+    influence: Option<u8>,
+    certain: u8,
+    erasure: Option<u8>,
+    teams: bool,
+    toroidal: bool,
+    hex: bool,
+    gravity: bool,
+    handicap: Option<HandicapJS>,
+}
+
+#[derive(serde::Deserialize)]
+struct DecoherenceJS {
+    certain: bool,
+    step: u8,
+}
+
+#[derive(serde::Deserialize)]
+struct HandicapJS {
+    white: bool,
+    stones: u8,
+    certain: bool,
+}
+
+impl From<RulesJS> for Rules {
+    fn from(rules: RulesJS) -> Self {
+        Rules {
+            allow_entanglement: rules.entanglement,
+            allow_superposition: rules.superposition,
+            stone_model: if rules.amplitude {
+                StoneModelKind::Amplitude
+            } else {
+                StoneModelKind::Classical
+            },
+            regional_observation: rules.regional,
+            persistent_collapse: rules.persistent,
+            decoherence: rules
+                .decoherence
+                .filter(|decoherence| decoherence.step > 0)
+                .map(|decoherence| Decoherence {
+                    toward: if decoherence.certain {
+                        Drift::Certain
+                    } else {
+                        Drift::Even
+                    },
+                    step: decoherence.step,
+                }),
+            influence: rules.influence,
+            certain_stones: rules.certain,
+            erasure: rules.erasure,
+            teams: rules.teams,
+            topology: if rules.toroidal {
+                Topology::Toroidal
+            } else {
+                Topology::Bounded
+            },
+            grid: if rules.hex { Grid::Hex } else { Grid::Square },
+            gravity: rules.gravity,
+            handicap: rules
+                .handicap
+                .filter(|handicap| handicap.stones > 0)
+                .map(|handicap| Handicap {
+                    player: if handicap.white {
+                        PlayerKind::White
+                    } else {
+                        PlayerKind::Black
+                    },
+                    stones: handicap.stones.min(MAX_HANDICAP),
+                    stone: if handicap.certain {
+                        PColor::Certain
+                    } else {
+                        PColor::Strong
+                    },
+                }),
+            ..Rules::default()
         }
     }
 }
 
 #[tauri::command]
 async fn init_game<R: Runtime>(
+    rules: Option<RulesJS>,
     state: tauri::State<'_, MyState>,
     window: tauri::Window<R>,
 ) -> Result<(), String> {
+    let rules: Rules = rules.unwrap_or_default().into();
+    rules.validate().map_err(|err| err.to_string())?;
+
     let mut game = state.game.lock().unwrap();
-    game.borrow_mut().reset();
+    *game = Game::with_rules(rules);
 
-    window
-        .emit("turn", game.turn().to_string())
-        .map_err(|err| err.to_string())?;
+    emit_turn(&window, &game)?;
 
     Ok(())
 }
@@ -69,22 +213,69 @@ async fn get_board_size() -> usize {
     BOARD_SIZE
 }
 
+#[derive(serde::Serialize)]
+struct CellLayoutJS {
+    x: usize,
+    y: usize,
+    cx: f64,
+    cy: f64,
+    label: String,
+}
+
+#[derive(serde::Serialize)]
+struct BoardLayoutJS {
+    grid: String,
+    cells: Vec<CellLayoutJS>,
+}
+
+// Where each cell is drawn and how it is labelled, so that the frontend does
+// not need to know the geometry of the grid.
+#[tauri::command]
+async fn get_board_layout(state: tauri::State<'_, MyState>) -> Result<BoardLayoutJS, String> {
+    let game = state.game.lock().unwrap();
+    let grid = game.rules().grid;
+
+    let cells = (0..BOARD_SIZE)
+        .flat_map(|y| (0..BOARD_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (cx, cy) = grid.center(x, y);
+            CellLayoutJS {
+                x,
+                y,
+                cx,
+                cy,
+                label: grid.notation(x, y),
+            }
+        })
+        .collect();
+
+    Ok(BoardLayoutJS {
+        grid: match grid {
+            Grid::Square => "square",
+            Grid::Hex => "hex",
+        }
+        .to_string(),
+        cells,
+    })
+}
+
 type PBoardJS = [[Option<u8>; BOARD_SIZE]; BOARD_SIZE];
 
 #[tauri::command]
 async fn get_board(state: tauri::State<'_, MyState>) -> Result<PBoardJS, String> {
     let game = state.game.lock().unwrap();
-    let board = game.get_p_board();
 
-    Ok(convert_board(board))
+    Ok(convert_board(&game))
 }
 
-fn convert_board(board: &[[Option<Color>; BOARD_SIZE]; BOARD_SIZE]) -> PBoardJS {
+fn convert_board(game: &Game) -> PBoardJS {
     let mut result = [[None; BOARD_SIZE]; BOARD_SIZE];
 
-    for y in 0..BOARD_SIZE {
-        for x in 0..BOARD_SIZE {
-            result[y][x] = board[y][x].map(|color| color.into());
+    for (y, row) in result.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = game
+                .black_probability(x, y)
+                .map(|p| (p * 100.0).round() as u8);
         }
     }
 
@@ -95,6 +286,33 @@ fn convert_board(board: &[[Option<Color>; BOARD_SIZE]; BOARD_SIZE]) -> PBoardJS
 struct TurnInfo {
     player: String,
     p: u8,
+    certain_stones: u8,
+    teams: bool,
+    member: u8,
+}
+
+fn emit_turn<R: Runtime>(window: &tauri::Window<R>, game: &Game) -> Result<(), String> {
+    window
+        .emit(
+            "turn",
+            TurnInfo {
+                player: game.turn().to_string(),
+                p: game.get_turn_p(),
+                certain_stones: game.player(game.turn()).certain_stones_left(),
+                teams: game.rules().teams,
+                member: game.player(game.turn()).member(),
+            },
+        )
+        .map_err(|err| err.to_string())
+}
+
+fn emit_winner<R: Runtime>(window: &tauri::Window<R>, game: &Game) -> Result<(), String> {
+    match game.winner() {
+        Some(winner) => window
+            .emit("winner", winner.to_string())
+            .map_err(|err| err.to_string()),
+        None => Ok(()),
+    }
 }
 
 #[tauri::command]
@@ -110,68 +328,819 @@ async fn place_stone<R: Runtime>(
         .place_stone_prohibitly(x, y)
         .map_err(|err| err.to_string())?;
 
-    window
-        .emit(
-            "turn",
-            TurnInfo {
-                player: game.turn().to_string(),
-                p: game.get_turn_p(),
+    emit_turn(&window, &game)?;
+
+    Ok(u8::from(color))
+}
+
+#[derive(serde::Serialize, Clone)]
+struct DropJS {
+    x: usize,
+    y: usize,
+    p: u8,
+}
+
+#[tauri::command]
+async fn drop_stone<R: Runtime>(
+    column: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<DropJS, String> {
+    let mut game = state.game.lock().unwrap();
+    let (x, y, color) = game.drop_stone(column).map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+
+    Ok(DropJS {
+        x,
+        y,
+        p: u8::from(color),
+    })
+}
+
+#[derive(serde::Serialize, Clone)]
+struct EntanglementJS {
+    first: (usize, usize),
+    second: (usize, usize),
+    anticorrelated: bool,
+}
+
+impl From<&Entanglement> for EntanglementJS {
+    fn from(entanglement: &Entanglement) -> Self {
+        EntanglementJS {
+            first: entanglement.first,
+            second: entanglement.second,
+            anticorrelated: entanglement.correlation == Correlation::Anticorrelated,
+        }
+    }
+}
+
+#[tauri::command]
+async fn place_entangled<R: Runtime>(
+    x1: usize,
+    y1: usize,
+    x2: usize,
+    y2: usize,
+    anticorrelated: bool,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<(u8, u8), String> {
+    let mut game = state.game.lock().unwrap();
+    let (first, second) = game
+        .place_entangled(Entanglement {
+            first: (x1, y1),
+            second: (x2, y2),
+            correlation: if anticorrelated {
+                Correlation::Anticorrelated
+            } else {
+                Correlation::Correlated
             },
-        )
+        })
+        .map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+
+    Ok((u8::from(first), u8::from(second)))
+}
+
+#[tauri::command]
+async fn get_entanglements(
+    state: tauri::State<'_, MyState>,
+) -> Result<Vec<EntanglementJS>, String> {
+    let game = state.game.lock().unwrap();
+
+    Ok(game
+        .entanglements()
+        .iter()
+        .map(EntanglementJS::from)
+        .collect())
+}
+
+#[derive(serde::Serialize, Clone)]
+struct SuperpositionJS {
+    cells: [(usize, usize); 2],
+    p: u8,
+}
+
+impl SuperpositionJS {
+    // The probability comes from the stone model, which may have drifted it.
+    fn new(superposition: &Superposition, black_probability: f64) -> Self {
+        SuperpositionJS {
+            cells: superposition.cells,
+            p: (black_probability * 100.0).round() as u8,
+        }
+    }
+}
+
+#[tauri::command]
+async fn place_superposed<R: Runtime>(
+    x1: usize,
+    y1: usize,
+    x2: usize,
+    y2: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<u8, String> {
+    let mut game = state.game.lock().unwrap();
+    let color = game
+        .place_superposed([(x1, y1), (x2, y2)])
         .map_err(|err| err.to_string())?;
 
+    emit_turn(&window, &game)?;
+
     Ok(u8::from(color))
 }
 
-type ObservedBoardJS = [[Option<u8>; BOARD_SIZE]; BOARD_SIZE];
+#[tauri::command]
+async fn get_superpositions(
+    state: tauri::State<'_, MyState>,
+) -> Result<Vec<SuperpositionJS>, String> {
+    let game = state.game.lock().unwrap();
+
+    Ok(game
+        .superpositions()
+        .iter()
+        .enumerate()
+        .map(|(index, superposition)| {
+            SuperpositionJS::new(superposition, game.superposed_black_probability(index))
+        })
+        .collect())
+}
 
 #[tauri::command]
-async fn observe<R: Runtime>(
+async fn flip<R: Runtime>(
+    x: usize,
+    y: usize,
+    state: tauri::State<'_, MyState>,
     window: tauri::Window<R>,
+) -> Result<PBoardJS, String> {
+    let mut game = state.game.lock().unwrap();
+    game.flip(x, y).map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+
+    Ok(convert_board(&game))
+}
+
+#[tauri::command]
+async fn erase<R: Runtime>(
+    x: usize,
+    y: usize,
     state: tauri::State<'_, MyState>,
-) -> Result<ObservedBoardJS, String> {
+    window: tauri::Window<R>,
+) -> Result<PBoardJS, String> {
+    let mut game = state.game.lock().unwrap();
+    game.erase(x, y).map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+
+    Ok(convert_board(&game))
+}
+
+#[tauri::command]
+async fn place_influencer<R: Runtime>(
+    x: usize,
+    y: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<u8, String> {
     let mut game = state.game.lock().unwrap();
+    let color = game.place_influencer(x, y).map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
 
-    let board = game.observe();
+    Ok(u8::from(color))
+}
 
-    let result = board.map(|row| {
+#[tauri::command]
+async fn place_certain<R: Runtime>(
+    x: usize,
+    y: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<u8, String> {
+    let mut game = state.game.lock().unwrap();
+    let color = game.place_certain(x, y).map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+
+    Ok(u8::from(color))
+}
+
+#[tauri::command]
+async fn get_influencers(state: tauri::State<'_, MyState>) -> Result<Vec<(usize, usize)>, String> {
+    let game = state.game.lock().unwrap();
+
+    Ok(game.influencers().iter().collect())
+}
+
+type ObservedBoardJS = [[Option<u8>; BOARD_SIZE]; BOARD_SIZE];
+
+fn convert_observed_board(board: &ObservedBoard) -> ObservedBoardJS {
+    board.map(|row| {
         row.map(|color| {
             color.map(|color| match color {
                 PlayerKind::Black => 100,
                 PlayerKind::White => 0,
             })
         })
-    });
+    })
+}
 
-    window
-        .emit("turn", TurnInfo {
-            player: game.turn().to_string(),
-            p: game.get_turn_p(),
+#[tauri::command]
+async fn observe<R: Runtime>(
+    window: tauri::Window<R>,
+    state: tauri::State<'_, MyState>,
+) -> Result<ObservedBoardJS, String> {
+    let mut game = state.game.lock().unwrap();
+
+    let board = game.try_observe().map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+    emit_winner(&window, &game)?;
+
+    Ok(convert_observed_board(&board))
+}
+
+#[tauri::command]
+async fn observe_region<R: Runtime>(
+    region: String,
+    window: tauri::Window<R>,
+    state: tauri::State<'_, MyState>,
+) -> Result<ObservedBoardJS, String> {
+    let region = parse_region(&region).map_err(|err| err.to_string())?;
+    let mut game = state.game.lock().unwrap();
+
+    let board = game
+        .try_observe_region(region)
+        .map_err(|err| err.to_string())?;
+
+    emit_turn(&window, &game)?;
+    emit_winner(&window, &game)?;
+
+    Ok(convert_observed_board(&board))
+}
+
+#[tauri::command]
+async fn get_observed_board(state: tauri::State<'_, MyState>) -> Result<ObservedBoardJS, String> {
+    let game = state.game.lock().unwrap();
+
+    Ok(convert_observed_board(&game.get_observed_board()))
+}
+
+fn parse_player(player: &str) -> Result<PlayerKind, String> {
+    match player {
+        "black" => Ok(PlayerKind::Black),
+        "white" => Ok(PlayerKind::White),
+        _ => Err(format!("Invalid player: {}", player)),
+    }
+}
+
+// Starts editing from the current position of the game.
+#[tauri::command]
+async fn begin_setup(state: tauri::State<'_, MyState>) -> Result<PBoardJS, String> {
+    // The game lock is released before taking the setup lock, which the other
+    // setup commands always take first.
+    let position = Position::from_game(&state.game.lock().unwrap());
+    *state.setup.lock().unwrap() = Some(position);
+
+    Ok(position
+        .stones()
+        .to_array()
+        .map(|row| row.map(|color| color.map(u8::from))))
+}
+
+#[tauri::command]
+async fn setup_cell(
+    x: usize,
+    y: usize,
+    p: Option<u8>,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    let color = match p {
+        Some(p) => Some(Color::from_p(p).ok_or(format!("Invalid probability: {}", p))?),
+        None => None,
+    };
+    let mut setup = state.setup.lock().unwrap();
+    let position = setup.as_mut().ok_or("No position being set up")?;
+
+    position
+        .set_stone(x, y, color)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+async fn setup_turn(player: String, state: tauri::State<'_, MyState>) -> Result<(), String> {
+    let mut setup = state.setup.lock().unwrap();
+    let position = setup.as_mut().ok_or("No position being set up")?;
+    position.set_turn(parse_player(&player)?);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn setup_next_stone(
+    player: String,
+    strong: bool,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    let mut setup = state.setup.lock().unwrap();
+    let position = setup.as_mut().ok_or("No position being set up")?;
+    let stone = if strong { PColor::Strong } else { PColor::Weak };
+    position.set_next_stone(parse_player(&player)?, stone);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn validate_setup(state: tauri::State<'_, MyState>) -> Result<(), String> {
+    let setup = state.setup.lock().unwrap();
+    let position = setup.as_ref().ok_or("No position being set up")?;
+    let game = state.game.lock().unwrap();
+
+    position
+        .validate(game.rules())
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+async fn start_from_setup<R: Runtime>(
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<(), String> {
+    let mut setup = state.setup.lock().unwrap();
+    let position = setup.as_ref().ok_or("No position being set up")?;
+    let mut game = state.game.lock().unwrap();
+
+    *game = Game::from_position(*game.rules(), position).map_err(|err| err.to_string())?;
+    *setup = None;
+
+    emit_turn(&window, &game)?;
+
+    Ok(())
+}
+
+fn progress_path<R: Runtime>(window: &tauri::Window<R>) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("No app data directory")?;
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    Ok(dir.join("puzzles.txt"))
+}
+
+#[derive(serde::Serialize)]
+struct PuzzleJS {
+    id: String,
+    title: String,
+    goal: String,
+    percent: u8,
+    attempts: u32,
+    solved: bool,
+}
+
+#[tauri::command]
+async fn get_puzzles<R: Runtime>(window: tauri::Window<R>) -> Result<Vec<PuzzleJS>, String> {
+    let progress = Progress::load(progress_path(&window)?).map_err(|err| err.to_string())?;
+
+    Ok(puzzle::puzzles()
+        .into_iter()
+        .map(|puzzle| {
+            let (goal, percent) = match puzzle.goal {
+                Goal::Win { percent } => ("win", percent),
+                Goal::Block { percent } => ("block", percent),
+            };
+            PuzzleJS {
+                id: puzzle.id.to_string(),
+                title: puzzle.title.to_string(),
+                goal: goal.to_string(),
+                percent,
+                attempts: progress.attempts(puzzle.id),
+                solved: progress.is_solved(puzzle.id),
+            }
         })
+        .collect())
+}
+
+#[tauri::command]
+async fn start_puzzle<R: Runtime>(
+    id: String,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<(), String> {
+    let puzzle = puzzle::find(&id).ok_or(format!("No such puzzle: {}", id))?;
+    let mut game = state.game.lock().unwrap();
+    *game = puzzle.game();
+
+    emit_turn(&window, &game)?;
+
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct VerdictJS {
+    solved: bool,
+    probability: f64,
+}
+
+// The answer is an action as the engine protocol writes it, e.g. `9,9` or
+// `ERASE 8,14`.
+#[tauri::command]
+async fn answer_puzzle<R: Runtime>(
+    id: String,
+    answer: String,
+    window: tauri::Window<R>,
+) -> Result<VerdictJS, String> {
+    let puzzle = puzzle::find(&id).ok_or(format!("No such puzzle: {}", id))?;
+    let answer = parse_action(&answer).map_err(|err| err.to_string())?;
+    let verdict = puzzle
+        .check(answer, puzzle::SAMPLES, &mut rand::thread_rng())
         .map_err(|err| err.to_string())?;
 
-    let winners = game.get_winners();
-    dbg!(winners.clone());
-    match winners.len() {
-        1 => {
-            let winner = winners.iter().next().unwrap();
-            window
-                .emit("winner", winner.to_string())
+    let path = progress_path(&window)?;
+    let mut progress = Progress::load(&path).map_err(|err| err.to_string())?;
+    progress.record(puzzle.id, verdict.solved);
+    progress.save(&path).map_err(|err| err.to_string())?;
+
+    Ok(VerdictJS {
+        solved: verdict.solved,
+        probability: verdict.probability,
+    })
+}
+
+#[tauri::command]
+async fn connect_engine(
+    command: String,
+    state: tauri::State<'_, MyState>,
+) -> Result<String, String> {
+    let engine = ExternalEngine::spawn(&command).map_err(|err| err.to_string())?;
+    let name = engine.name().to_string();
+
+    *state.engine.lock().unwrap() = Some(engine);
+
+    Ok(name)
+}
+
+#[derive(serde::Serialize, Clone)]
+struct MultiTurnInfo {
+    seat: usize,
+    p: Vec<u8>,
+}
+
+fn emit_multiplayer_turn<R: Runtime>(
+    window: &tauri::Window<R>,
+    game: &MultiGame,
+) -> Result<(), String> {
+    window
+        .emit(
+            "multiplayer-turn",
+            MultiTurnInfo {
+                seat: game.turn(),
+                p: game.turn_distribution().0[..game.players()].to_vec(),
+            },
+        )
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+async fn init_multiplayer<R: Runtime>(
+    players: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<(), String> {
+    let game = MultiGame::with_rules(MultiRules {
+        players,
+        ..MultiRules::default()
+    })
+    .map_err(|err| err.to_string())?;
+
+    emit_multiplayer_turn(&window, &game)?;
+    *state.multiplayer.lock().unwrap() = Some(game);
+
+    Ok(())
+}
+
+type MultiBoardJS = [[Option<Vec<u8>>; BOARD_SIZE]; BOARD_SIZE];
+
+#[tauri::command]
+async fn get_multiplayer_board(state: tauri::State<'_, MyState>) -> Result<MultiBoardJS, String> {
+    let game = state.multiplayer.lock().unwrap();
+    let game = game.as_ref().ok_or("No multiplayer game")?;
+
+    Ok(std::array::from_fn(|y| {
+        std::array::from_fn(|x| {
+            game.stone(x, y)
+                .map(|(_, distribution)| distribution.0[..game.players()].to_vec())
+        })
+    }))
+}
+
+#[tauri::command]
+async fn place_multiplayer<R: Runtime>(
+    x: usize,
+    y: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<Vec<u8>, String> {
+    let mut game = state.multiplayer.lock().unwrap();
+    let game = game.as_mut().ok_or("No multiplayer game")?;
+    let distribution = game.place_stone(x, y).map_err(|err| err.to_string())?;
+
+    emit_multiplayer_turn(&window, game)?;
+
+    Ok(distribution.0[..game.players()].to_vec())
+}
+
+#[tauri::command]
+async fn observe_multiplayer<R: Runtime>(
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<SeatBoard, String> {
+    let mut game = state.multiplayer.lock().unwrap();
+    let game = game.as_mut().ok_or("No multiplayer game")?;
+    let board = game.observe().map_err(|err| err.to_string())?;
+
+    emit_multiplayer_turn(&window, game)?;
+    if let Some(winner) = game.winner() {
+        window
+            .emit("multiplayer-winner", winner)
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(board)
+}
+
+#[derive(serde::Serialize, Clone)]
+struct CubeTurnInfo {
+    player: String,
+    p: u8,
+}
+
+fn emit_cube_turn<R: Runtime>(window: &tauri::Window<R>, game: &CubeGame) -> Result<(), String> {
+    window
+        .emit(
+            "cube-turn",
+            CubeTurnInfo {
+                player: game.turn().to_string(),
+                p: game.get_turn_p(),
+            },
+        )
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+async fn init_cube<R: Runtime>(
+    size: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<(), String> {
+    let game = CubeGame::with_rules(CubeRules {
+        lattice: Lattice::cube(size),
+        ..CubeRules::default()
+    })
+    .map_err(|err| err.to_string())?;
+
+    emit_cube_turn(&window, &game)?;
+    *state.cube.lock().unwrap() = Some(game);
+
+    Ok(())
+}
+
+// Layers of rows of cells, indexed [z][y][x].
+type CubeBoardJS = Vec<Vec<Vec<Option<u8>>>>;
+
+fn convert_cube_board(lattice: Lattice, cell: impl Fn(usize) -> Option<u8>) -> CubeBoardJS {
+    (0..lattice.layers)
+        .map(|z| {
+            (0..lattice.size)
+                .map(|y| {
+                    (0..lattice.size)
+                        .map(|x| cell(lattice.index([x, y, z])))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn get_cube_board(state: tauri::State<'_, MyState>) -> Result<CubeBoardJS, String> {
+    let game = state.cube.lock().unwrap();
+    let game = game.as_ref().ok_or("No cube game")?;
+    let lattice = game.lattice();
+
+    Ok(convert_cube_board(lattice, |index| {
+        game.stone(lattice.point(index)).map(u8::from)
+    }))
+}
+
+#[tauri::command]
+async fn place_cube<R: Runtime>(
+    x: usize,
+    y: usize,
+    z: usize,
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<u8, String> {
+    let mut game = state.cube.lock().unwrap();
+    let game = game.as_mut().ok_or("No cube game")?;
+    let color = game.place_stone([x, y, z]).map_err(|err| err.to_string())?;
+
+    emit_cube_turn(&window, game)?;
+
+    Ok(u8::from(color))
+}
+
+#[tauri::command]
+async fn observe_cube<R: Runtime>(
+    state: tauri::State<'_, MyState>,
+    window: tauri::Window<R>,
+) -> Result<CubeBoardJS, String> {
+    let mut game = state.cube.lock().unwrap();
+    let game = game.as_mut().ok_or("No cube game")?;
+    let board = game.observe().map_err(|err| err.to_string())?;
+
+    emit_cube_turn(&window, game)?;
+    if let Some(winner) = game.winner() {
+        window
+            .emit("cube-winner", winner.to_string())
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(convert_cube_board(game.lattice(), |index| {
+        board[index].map(|kind| match kind {
+            PlayerKind::Black => 100,
+            PlayerKind::White => 0,
+        })
+    }))
+}
+
+#[derive(serde::Serialize)]
+struct WinProbabilityJS {
+    black: f64,
+    white: f64,
+    none: f64,
+}
+
+#[tauri::command]
+async fn get_cube_outcome(
+    samples: usize,
+    state: tauri::State<'_, MyState>,
+) -> Result<WinProbabilityJS, String> {
+    let game = state.cube.lock().unwrap();
+    let game = game.as_ref().ok_or("No cube game")?;
+    let outcome = cube_observation_outcome(game, samples, &mut rand::thread_rng());
+
+    Ok(WinProbabilityJS {
+        black: outcome.black,
+        white: outcome.white,
+        none: outcome.none,
+    })
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum EngineMoveJS {
+    Place {
+        x: usize,
+        y: usize,
+        p: u8,
+    },
+    Entangle {
+        pair: EntanglementJS,
+        p: (u8, u8),
+    },
+    Superpose {
+        stone: SuperpositionJS,
+    },
+    Flip {
+        x: usize,
+        y: usize,
+        board: PBoardJS,
+    },
+    Influence {
+        x: usize,
+        y: usize,
+        p: u8,
+    },
+    Erase {
+        x: usize,
+        y: usize,
+        board: PBoardJS,
+    },
+    Observe {
+        board: ObservedBoardJS,
+    },
+    ObserveRegion {
+        region: String,
+        board: ObservedBoardJS,
+    },
+}
+
+#[tauri::command]
+async fn engine_turn<R: Runtime>(
+    window: tauri::Window<R>,
+    state: tauri::State<'_, MyState>,
+) -> Result<EngineMoveJS, String> {
+    // The engine thinks on a copy, so the game stays unlocked meanwhile.
+    let snapshot = state.game.lock().unwrap().clone();
+    let action = {
+        let mut engine = state.engine.lock().unwrap();
+        let engine = engine.as_mut().ok_or("No engine is connected")?;
+        engine
+            .select_action(&snapshot)
+            .map_err(|err| err.to_string())?
+    };
+
+    let mut game = state.game.lock().unwrap();
+    if game.hash() != snapshot.hash() || game.history().len() != snapshot.history().len() {
+        return Err("The game changed while the engine was thinking".to_string());
+    }
+
+    let result = match action {
+        Action::Place(x, y) => {
+            let color = game
+                .place_stone_prohibitly(x, y)
                 .map_err(|err| err.to_string())?;
+            EngineMoveJS::Place {
+                x,
+                y,
+                p: u8::from(color),
+            }
         }
-
-        2 => {
-            let who_observed = match game.turn() {
-                PlayerKind::Black => PlayerKind::White,
-                PlayerKind::White => PlayerKind::Black,
-            };
-            window
-                .emit("winner", who_observed.to_string())
+        Action::PlaceEntangled(entanglement) => {
+            let (first, second) = game
+                .place_entangled(entanglement)
+                .map_err(|err| err.to_string())?;
+            EngineMoveJS::Entangle {
+                pair: EntanglementJS::from(&entanglement),
+                p: (u8::from(first), u8::from(second)),
+            }
+        }
+        Action::PlaceSuperposed(cells) => {
+            let color = game
+                .place_superposed(cells)
+                .map_err(|err| err.to_string())?;
+            EngineMoveJS::Superpose {
+                stone: SuperpositionJS {
+                    cells,
+                    p: u8::from(color),
+                },
+            }
+        }
+        Action::Flip(x, y) => {
+            game.flip(x, y).map_err(|err| err.to_string())?;
+            EngineMoveJS::Flip {
+                x,
+                y,
+                board: convert_board(&game),
+            }
+        }
+        Action::PlaceInfluencer(x, y) => {
+            let color = game.place_influencer(x, y).map_err(|err| err.to_string())?;
+            EngineMoveJS::Influence {
+                x,
+                y,
+                p: u8::from(color),
+            }
+        }
+        Action::Erase(x, y) => {
+            game.erase(x, y).map_err(|err| err.to_string())?;
+            EngineMoveJS::Erase {
+                x,
+                y,
+                board: convert_board(&game),
+            }
+        }
+        Action::PlaceCertain(x, y) => {
+            let color = game.place_certain(x, y).map_err(|err| err.to_string())?;
+            EngineMoveJS::Place {
+                x,
+                y,
+                p: u8::from(color),
+            }
+        }
+        Action::Observe => {
+            let board = game.try_observe().map_err(|err| err.to_string())?;
+            EngineMoveJS::Observe {
+                board: convert_observed_board(&board),
+            }
+        }
+        Action::ObserveRegion(region) => {
+            let board = game
+                .try_observe_region(region)
                 .map_err(|err| err.to_string())?;
+            EngineMoveJS::ObserveRegion {
+                region: format_region(region),
+                board: convert_observed_board(&board),
+            }
         }
+    };
 
-        _ => (),
-    }
+    emit_turn(&window, &game)?;
+    emit_winner(&window, &game)?;
 
     Ok(result)
 }
@@ -0,0 +1,372 @@
+use rand::{thread_rng, Rng};
+
+use crate::{
+    bitboard::Bitboard,
+    game::{decide_seat_winner, GameError, TieBreak, BOARD_SIZE},
+    player::PColor,
+};
+
+pub const MAX_PLAYERS: usize = 4;
+
+pub type Seat = usize;
+pub type SeatBoard = [[Option<Seat>; BOARD_SIZE]; BOARD_SIZE];
+
+// Percent chance of each seat, summing to 100.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Distribution(pub [u8; MAX_PLAYERS]);
+
+impl Distribution {
+    // The owner's share is what a stone of that strength gives its own color
+    // in the two-player game, and the rest is split evenly between the other
+    // seats, the ones right after the owner taking any remainder.
+    pub fn for_stone(owner: Seat, stone: PColor, players: usize) -> Distribution {
+        let own: u8 = match stone {
+            PColor::Strong => 90,
+            PColor::Weak => 70,
+            PColor::Certain => 100,
+        };
+        let rest = 100 - own;
+        let others = (players - 1) as u8;
+
+        let mut shares = [0; MAX_PLAYERS];
+        shares[owner] = own;
+        for i in 1..players {
+            let seat = (owner + i) % players;
+            shares[seat] = rest / others + u8::from(((i - 1) as u8) < rest % others);
+        }
+
+        Distribution(shares)
+    }
+
+    pub fn probability(&self, seat: Seat) -> u8 {
+        self.0[seat]
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Seat {
+        let random_number = (rng.gen::<f64>() * 100.0).floor() as u8;
+        let mut total = 0;
+        for (seat, &share) in self.0.iter().enumerate() {
+            total += share;
+            if random_number < total {
+                return seat;
+            }
+        }
+
+        unreachable!("shares sum to 100")
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MultiRules {
+    pub players: usize,
+    pub observe_passes_turn: bool,
+    pub tie_break: TieBreak,
+    pub observation_limit: Option<u8>,
+}
+
+impl Default for MultiRules {
+    fn default() -> Self {
+        MultiRules {
+            players: 3,
+            observe_passes_turn: true,
+            tie_break: TieBreak::Observer,
+            observation_limit: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MultiMove {
+    Place {
+        x: usize,
+        y: usize,
+        seat: Seat,
+        distribution: Distribution,
+    },
+    Observe(Box<SeatBoard>),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SeatState {
+    next_stone: PColor,
+    observations_left: Option<u8>,
+}
+
+// Three or four seats on the flat board. `Game` only knows two colors, teams
+// included, so this keeps a board of its own and shares just the rule for
+// several fives at once, `decide_seat_winner`.
+#[derive(Clone)]
+pub struct MultiGame {
+    rules: MultiRules,
+    stones: Vec<Option<(Seat, Distribution)>>,
+    occupied: Bitboard,
+    observed: Vec<Bitboard>,
+    seats: Vec<SeatState>,
+    turn: Seat,
+    winner: Option<Seat>,
+    history: Vec<MultiMove>,
+}
+
+impl MultiGame {
+    pub fn with_rules(rules: MultiRules) -> Result<MultiGame, GameError> {
+        if !(2..=MAX_PLAYERS).contains(&rules.players) {
+            return Err(GameError::InvalidPlayerCount(rules.players));
+        }
+
+        // The first seat starts with the weaker stone, as black does.
+        let seats = (0..rules.players)
+            .map(|seat| SeatState {
+                next_stone: if seat == 0 {
+                    PColor::Weak
+                } else {
+                    PColor::Strong
+                },
+                observations_left: rules.observation_limit,
+            })
+            .collect();
+
+        Ok(MultiGame {
+            rules,
+            stones: vec![None; BOARD_SIZE * BOARD_SIZE],
+            occupied: Bitboard::EMPTY,
+            observed: vec![Bitboard::EMPTY; rules.players],
+            seats,
+            turn: 0,
+            winner: None,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn rules(&self) -> &MultiRules {
+        &self.rules
+    }
+
+    pub fn players(&self) -> usize {
+        self.rules.players
+    }
+
+    pub fn turn(&self) -> Seat {
+        self.turn
+    }
+
+    pub fn winner(&self) -> Option<Seat> {
+        self.winner
+    }
+
+    pub fn history(&self) -> &[MultiMove] {
+        &self.history
+    }
+
+    pub fn stone(&self, x: usize, y: usize) -> Option<(Seat, Distribution)> {
+        self.stones[y * BOARD_SIZE + x]
+    }
+
+    pub fn turn_distribution(&self) -> Distribution {
+        Distribution::for_stone(
+            self.turn,
+            self.seats[self.turn].next_stone,
+            self.rules.players,
+        )
+    }
+
+    pub fn observations_left(&self, seat: Seat) -> Option<u8> {
+        self.seats[seat].observations_left
+    }
+
+    pub fn can_observe(&self) -> bool {
+        self.winner.is_none() && self.seats[self.turn].observations_left != Some(0)
+    }
+
+    pub fn legal_placements(&self) -> Vec<(usize, usize)> {
+        (!self.occupied).iter().collect()
+    }
+
+    pub fn observed_board(&self) -> SeatBoard {
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (seat, stones) in self.observed.iter().enumerate() {
+            for (x, y) in stones.iter() {
+                board[y][x] = Some(seat);
+            }
+        }
+        board
+    }
+
+    fn take_turn(&mut self) {
+        self.turn = (self.turn + 1) % self.rules.players;
+    }
+
+    pub fn place_stone(&mut self, x: usize, y: usize) -> Result<Distribution, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if x >= BOARD_SIZE || y >= BOARD_SIZE || self.occupied.get(x, y) {
+            return Err(GameError::InvalidPosition(None));
+        }
+
+        let distribution = self.turn_distribution();
+        let state = &mut self.seats[self.turn];
+        state.next_stone = match state.next_stone {
+            PColor::Strong => PColor::Weak,
+            PColor::Weak => PColor::Strong,
+            PColor::Certain => PColor::Certain,
+        };
+
+        self.stones[y * BOARD_SIZE + x] = Some((self.turn, distribution));
+        self.occupied.set(x, y);
+        self.history.push(MultiMove::Place {
+            x,
+            y,
+            seat: self.turn,
+            distribution,
+        });
+
+        self.take_turn();
+
+        Ok(distribution)
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Bitboard> {
+        let mut observed = vec![Bitboard::EMPTY; self.rules.players];
+        for (x, y) in self.occupied.iter() {
+            if let Some((_, distribution)) = self.stone(x, y) {
+                observed[distribution.sample(rng)].set(x, y);
+            }
+        }
+        observed
+    }
+
+    pub fn observe(&mut self) -> Result<SeatBoard, GameError> {
+        self.observe_with(&mut thread_rng())
+    }
+
+    pub fn observe_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<SeatBoard, GameError> {
+        if self.winner.is_some() {
+            return Err(GameError::GameIsAlreadyOver);
+        }
+
+        if !self.can_observe() {
+            return Err(GameError::NoObservationsLeft);
+        }
+
+        let who_observed = self.turn;
+        if let Some(left) = self.seats[who_observed].observations_left.as_mut() {
+            *left = left.saturating_sub(1);
+        }
+
+        if self.rules.observe_passes_turn {
+            self.take_turn();
+        }
+
+        self.observed = self.sample(rng);
+        let board = self.observed_board();
+        self.history.push(MultiMove::Observe(Box::new(board)));
+
+        let winners: Vec<Seat> = (0..self.rules.players)
+            .filter(|&seat| self.observed[seat].has_five())
+            .collect();
+        self.winner = decide_seat_winner(
+            &winners,
+            who_observed,
+            self.rules.tie_break,
+            self.rules.players,
+        );
+
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{game::decide_winner, player::PlayerKind};
+
+    #[test]
+    fn three_seats_take_turns_in_order() {
+        let mut game = MultiGame::with_rules(MultiRules::default()).unwrap();
+
+        let mut seats = Vec::new();
+        for i in 0..6 {
+            seats.push(game.turn());
+            game.place_stone(i, 0).unwrap();
+        }
+        assert_eq!(seats, [0, 1, 2, 0, 1, 2]);
+
+        game.observe_with(&mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(game.turn(), 1);
+
+        let mut kept = MultiGame::with_rules(MultiRules {
+            observe_passes_turn: false,
+            ..MultiRules::default()
+        })
+        .unwrap();
+        kept.place_stone(0, 0).unwrap();
+        kept.observe_with(&mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(kept.turn(), 1);
+    }
+
+    #[test]
+    fn stones_are_split_between_the_other_seats() {
+        assert_eq!(
+            Distribution::for_stone(0, PColor::Weak, 3),
+            Distribution([70, 15, 15, 0])
+        );
+        assert_eq!(
+            Distribution::for_stone(2, PColor::Strong, 4),
+            Distribution([3, 3, 90, 4])
+        );
+    }
+
+    #[test]
+    fn sampling_follows_the_distribution() {
+        let distribution = Distribution::for_stone(1, PColor::Weak, 3);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut counts = [0; MAX_PLAYERS];
+        for _ in 0..10_000 {
+            counts[distribution.sample(&mut rng)] += 1;
+        }
+
+        assert_eq!(counts[3], 0);
+        for seat in 0..3 {
+            let share = counts[seat] as f64 / 10_000.0;
+            let expected = distribution.probability(seat) as f64 / 100.0;
+            assert!((share - expected).abs() < 0.02, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn several_fives_go_to_the_observer_or_the_next_winner() {
+        assert_eq!(decide_seat_winner(&[], 0, TieBreak::Observer, 3), None);
+        assert_eq!(decide_seat_winner(&[2], 0, TieBreak::Continue, 3), Some(2));
+
+        // Seats 1 and 2 both have a five.
+        assert_eq!(
+            decide_seat_winner(&[1, 2], 2, TieBreak::Observer, 3),
+            Some(2)
+        );
+        assert_eq!(
+            decide_seat_winner(&[1, 2], 0, TieBreak::Observer, 3),
+            Some(1)
+        );
+        assert_eq!(
+            decide_seat_winner(&[1, 2], 2, TieBreak::Opponent, 3),
+            Some(1)
+        );
+        assert_eq!(decide_seat_winner(&[1, 2], 1, TieBreak::Continue, 3), None);
+
+        // The same rule with two players.
+        let both = HashSet::from([PlayerKind::Black, PlayerKind::White]);
+        let white = PlayerKind::White;
+        assert_eq!(decide_winner(&both, white, TieBreak::Observer), Some(white));
+        assert_eq!(
+            decide_winner(&both, white, TieBreak::Opponent),
+            Some(PlayerKind::Black)
+        );
+        assert_eq!(decide_winner(&both, white, TieBreak::Continue), None);
+    }
+}
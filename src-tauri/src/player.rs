@@ -10,6 +10,7 @@ pub enum Color {
 pub enum PColor {
     Strong,
     Weak,
+    Certain,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -18,9 +19,13 @@ pub enum PlayerKind {
     White,
 }
 
+#[derive(Clone, Debug)]
 pub struct Player {
     kind: PlayerKind,
     next_stone: PColor,
+    observations_left: Option<u8>,
+    certain_stones_left: u8,
+    member: u8,
 }
 
 impl From<Color> for u8 {
@@ -29,38 +34,120 @@ impl From<Color> for u8 {
             Color::Black(color) => match color {
                 PColor::Strong => 90,
                 PColor::Weak => 70,
+                PColor::Certain => 100,
             },
             Color::White(color) => match color {
                 PColor::Strong => 10,
                 PColor::Weak => 30,
+                PColor::Certain => 0,
             },
         }
     }
 }
 
+impl Color {
+    // The inverse of `u8::from`.
+    pub fn from_p(p: u8) -> Option<Color> {
+        match p {
+            90 => Some(Color::Black(PColor::Strong)),
+            70 => Some(Color::Black(PColor::Weak)),
+            100 => Some(Color::Black(PColor::Certain)),
+            10 => Some(Color::White(PColor::Strong)),
+            30 => Some(Color::White(PColor::Weak)),
+            0 => Some(Color::White(PColor::Certain)),
+            _ => None,
+        }
+    }
+}
+
 impl Player {
     pub fn new(kind: PlayerKind, init_stone: PColor) -> Player {
         Player {
             kind,
             next_stone: init_stone,
+            observations_left: None,
+            certain_stones_left: 0,
+            member: 0,
         }
     }
 
+    pub fn with_observation_limit(mut self, limit: Option<u8>) -> Player {
+        self.observations_left = limit;
+        self
+    }
+
+    pub fn with_certain_stones(mut self, count: u8) -> Player {
+        self.certain_stones_left = count;
+        self
+    }
+
+    // Which of the two teammates this is in a team game.
+    pub fn with_member(mut self, member: u8) -> Player {
+        self.member = member;
+        self
+    }
+
     pub fn consume_stone(&mut self) -> PColor {
         let stone = self.next_stone;
         self.next_stone = match self.next_stone {
             PColor::Strong => PColor::Weak,
             PColor::Weak => PColor::Strong,
+            PColor::Certain => PColor::Certain,
         };
         stone
     }
 
+    // A certain stone costs the stone it replaces: the cycle moves on as if
+    // that stone had been placed.
+    pub fn consume_certain_stone(&mut self) -> PColor {
+        self.certain_stones_left = self.certain_stones_left.saturating_sub(1);
+        self.consume_stone();
+        PColor::Certain
+    }
+
+    pub fn kind(&self) -> PlayerKind {
+        self.kind
+    }
+
+    pub fn member(&self) -> u8 {
+        self.member
+    }
+
     pub fn next_stone(&self) -> PColor {
         self.next_stone
     }
+
+    pub fn observations_left(&self) -> Option<u8> {
+        self.observations_left
+    }
+
+    pub fn certain_stones_left(&self) -> u8 {
+        self.certain_stones_left
+    }
+
+    pub fn can_place_certain(&self) -> bool {
+        self.certain_stones_left > 0
+    }
+
+    pub fn can_observe(&self) -> bool {
+        self.observations_left != Some(0)
+    }
+
+    pub fn consume_observation(&mut self) {
+        if let Some(left) = self.observations_left.as_mut() {
+            *left = left.saturating_sub(1);
+        }
+    }
 }
 
 impl Color {
+    pub fn complement(&self) -> Color {
+        match *self {
+            Color::Black(p) => Color::White(p),
+            Color::White(p) => Color::Black(p),
+        }
+    }
+
     pub fn as_player_kind(&self) -> PlayerKind {
         match self {
             Color::Black(_) => PlayerKind::Black,
@@ -69,13 +156,21 @@ impl Color {
     }
 }
 
-impl ToString for PlayerKind {
-    fn to_string(&self) -> String {
+impl PlayerKind {
+    pub fn opponent(&self) -> PlayerKind {
+        match self {
+            PlayerKind::Black => PlayerKind::White,
+            PlayerKind::White => PlayerKind::Black,
+        }
+    }
+}
+
+impl Display for PlayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            PlayerKind::Black => "black",
-            PlayerKind::White => "white",
+            PlayerKind::Black => write!(f, "black"),
+            PlayerKind::White => write!(f, "white"),
         }
-        .to_string()
     }
 }
 
@@ -0,0 +1,141 @@
+use crate::{
+    bitboard::StoneBoard,
+    game::{Game, GameError, Rules, BOARD_SIZE},
+    player::{Color, PColor, PlayerKind},
+};
+
+// A position set up by hand in the editor, to play on from. It only holds
+// plain stones; superposed and entangled stones cannot be set up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position {
+    stones: StoneBoard,
+    turn: PlayerKind,
+    next_stones: [PColor; 2],
+}
+
+impl Position {
+    pub fn new(rules: &Rules) -> Position {
+        Position {
+            stones: StoneBoard::default(),
+            turn: rules.first_player(),
+            next_stones: [rules.black_first_stone, rules.white_first_stone],
+        }
+    }
+
+    // Superposed stones are left out and entangled stones lose their partner.
+    // Stones a persistent collapse has settled come out as certain stones.
+    pub fn from_game(game: &Game) -> Position {
+        let mut stones = *game.stones();
+        for (x, y) in stones.occupied().iter() {
+            match game.black_probability(x, y) {
+                Some(p) if p >= 1.0 => stones.set(x, y, Some(Color::Black(PColor::Certain))),
+                Some(p) if p <= 0.0 => stones.set(x, y, Some(Color::White(PColor::Certain))),
+                _ => (),
+            }
+        }
+
+        Position {
+            stones,
+            turn: game.turn(),
+            next_stones: [PlayerKind::Black, PlayerKind::White]
+                .map(|kind| game.player(kind).next_stone()),
+        }
+    }
+
+    pub fn stones(&self) -> &StoneBoard {
+        &self.stones
+    }
+
+    pub fn stone(&self, x: usize, y: usize) -> Option<Color> {
+        self.stones.get(x, y)
+    }
+
+    pub fn set_stone(&mut self, x: usize, y: usize, color: Option<Color>) -> Result<(), GameError> {
+        if x >= BOARD_SIZE || y >= BOARD_SIZE {
+            return Err(GameError::InvalidPosition(color));
+        }
+
+        self.stones.set(x, y, color);
+        Ok(())
+    }
+
+    pub fn turn(&self) -> PlayerKind {
+        self.turn
+    }
+
+    pub fn set_turn(&mut self, turn: PlayerKind) {
+        self.turn = turn;
+    }
+
+    pub fn next_stone(&self, kind: PlayerKind) -> PColor {
+        match kind {
+            PlayerKind::Black => self.next_stones[0],
+            PlayerKind::White => self.next_stones[1],
+        }
+    }
+
+    pub fn set_next_stone(&mut self, kind: PlayerKind, stone: PColor) {
+        match kind {
+            PlayerKind::Black => self.next_stones[0] = stone,
+            PlayerKind::White => self.next_stones[1] = stone,
+        }
+    }
+
+    pub fn validate(&self, rules: &Rules) -> Result<(), GameError> {
+        rules.validate()?;
+
+        for kind in [PlayerKind::Black, PlayerKind::White] {
+            // Certain stones come from the allowance, never from the cycle.
+            if self.next_stone(kind) == PColor::Certain {
+                return Err(GameError::InvalidNextStone(kind));
+            }
+        }
+
+        // A five of certain stones would win at the first observation.
+        for kind in [PlayerKind::Black, PlayerKind::White] {
+            let certain = match kind {
+                PlayerKind::Black => Color::Black(PColor::Certain),
+                PlayerKind::White => Color::White(PColor::Certain),
+            };
+            if self.stones.class(certain).has_five_on(rules.geometry()) {
+                return Err(GameError::CertainFive(kind));
+            }
+        }
+
+        if rules.gravity {
+            let occupied = self.stones.occupied();
+            for (x, y) in occupied.iter() {
+                if y + 1 < BOARD_SIZE && !occupied.get(x, y + 1) {
+                    return Err(GameError::UnsupportedStone(x, y));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ObservedBoard;
+
+    #[test]
+    fn settled_stones_are_set_up_as_certain_stones() {
+        let mut game = Game::with_rules(Rules {
+            persistent_collapse: true,
+            ..Rules::default()
+        });
+        game.place_stone_prohibitly(0, 0).unwrap();
+        game.place_stone_prohibitly(1, 0).unwrap();
+
+        let mut board: ObservedBoard = [[None; BOARD_SIZE]; BOARD_SIZE];
+        board[0][0] = Some(PlayerKind::White);
+        board[0][1] = Some(PlayerKind::White);
+        game.apply_observation(board).unwrap();
+
+        let position = Position::from_game(&game);
+        assert_eq!(position.stone(0, 0), Some(Color::White(PColor::Certain)));
+        assert_eq!(position.stone(1, 0), Some(Color::White(PColor::Certain)));
+    }
+}
@@ -0,0 +1,1177 @@
+//! Line-based text protocol between a host (the app, a tournament runner) and
+//! an engine process, modelled on Gomocup's piskvork protocol.
+//!
+//! The host sends one command per line and the engine answers with one line:
+//!
+//! | host                    | engine                                   |
+//! |-------------------------|------------------------------------------|
+//! | `ABOUT`                 | `name="...", version="..."`              |
+//! | `START <size>`          | `OK`                                     |
+//! | `RESTART`               | `OK`                                     |
+//! | `RULES <rule>...`       | `OK`                                     |
+//! | `SETUP <position>`      | `OK`                                     |
+//! | `PLAY <x>,<y>`          | `OK`                                     |
+//! | `ENTANGLE <pair>`       | `OK`                                     |
+//! | `SUPERPOSE <cells>`     | `OK`                                     |
+//! | `FLIP <x>,<y>`          | `OK`                                     |
+//! | `INFLUENCE <x>,<y>`     | `OK`                                     |
+//! | `CERTAIN <x>,<y>`       | `OK`                                     |
+//! | `ERASE <x>,<y>`         | `OK`                                     |
+//! | `OBSERVED <board> [<region>]` | `OK`                               |
+//! | `TURN`                  | `<x>,<y>`, `ENTANGLE ...`, `SUPERPOSE ...`, `FLIP ...`, `INFLUENCE ...`, `CERTAIN ...`, `ERASE ...` or `OBSERVE [<region>]` |
+//! | `END`                   | (exits)                                  |
+//!
+//! `RULES` starts a new game under the given rule variant, written as
+//! `<name>=<value>` tokens (see [`format_rules`]); rules left out keep their
+//! defaults. `START` and `RESTART` keep the current rules.
+//!
+//! `SETUP` starts a new game under the current rules from a position set up by
+//! hand, written `<side> <black-next> <white-next> <board>`: the side to move,
+//! each side's next stone in the cycle (`strong|weak`) and `BOARD_SIZE *
+//! BOARD_SIZE` row-major characters, `.` for an empty cell, `B`, `b` and `X`
+//! for a strong, weak and certain black stone and `W`, `w` and `O` for white.
+//! `RESTART` then goes back to that position.
+//!
+//! `PLAY`, `ENTANGLE`, `SUPERPOSE`, `FLIP`, `INFLUENCE`, `CERTAIN`, `ERASE`
+//! and `OBSERVED` are always played by the side to move, so the engine knows
+//! each stone's probability from the stone cycle. An entangled pair is written
+//! `<x1>,<y1> <x2>,<y2> correlated|anticorrelated` and a superposed stone
+//! `<x1>,<y1> <x2>,<y2>`, the two cells it may land on. `OBSERVED` carries the
+//! sampled outcome decided by the host as `BOARD_SIZE * BOARD_SIZE` row-major
+//! characters: `.` for an empty cell, `x` for black and `o` for white. Under
+//! regional observation it also names the observed region (see
+//! [`format_region`]) and the board holds the last seen state of every cell.
+//! An engine answering `TURN` does not apply its own action; the host echoes
+//! it back with `PLAY`, `ENTANGLE`, `SUPERPOSE`, `FLIP`, `INFLUENCE`, `CERTAIN`,
+//! `ERASE` or `OBSERVED`. Any failure is answered with `ERROR <message>`.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command as Process, Stdio},
+    str::FromStr,
+};
+
+use crate::{
+    ai::{Agent, AgentError},
+    game::{
+        Action, Cell, Correlation, Decoherence, Drift, Entanglement, Game, GameError, Handicap,
+        Move, ObservedBoard, Region, Rules, TieBreak, Topology, BOARD_SIZE, MAX_HANDICAP,
+    },
+    grid::Grid,
+    player::{Color, PColor, PlayerKind},
+    position::Position,
+    stone_model::StoneModelKind,
+    superposition,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    About,
+    Start(usize),
+    Restart,
+    Rules(Rules),
+    Setup(Box<Position>),
+    Play(usize, usize),
+    Entangle(Entanglement),
+    Superpose([(usize, usize); 2]),
+    Flip(usize, usize),
+    Influence(usize, usize),
+    Certain(usize, usize),
+    Erase(usize, usize),
+    Observed(Box<ObservedBoard>),
+    ObservedRegion(Region, Box<ObservedBoard>),
+    Turn,
+    End,
+}
+
+impl FromStr for Command {
+    type Err = ProtocolError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut tokens = line.split_whitespace();
+        let name = tokens
+            .next()
+            .ok_or_else(|| ProtocolError::Parse("empty command".to_string()))?
+            .to_ascii_uppercase();
+        if name == "RULES" {
+            return parse_rules(line.trim()[name.len()..].trim()).map(Command::Rules);
+        }
+        if name == "SETUP" {
+            return parse_setup(line.trim()[name.len()..].trim())
+                .map(|position| Command::Setup(Box::new(position)));
+        }
+        if name == "ENTANGLE" || name == "SUPERPOSE" {
+            return match parse_action(line.trim())? {
+                Action::PlaceEntangled(entanglement) => Ok(Command::Entangle(entanglement)),
+                Action::PlaceSuperposed(cells) => Ok(Command::Superpose(cells)),
+                _ => Err(ProtocolError::Parse(format!("invalid pair: {}", line))),
+            };
+        }
+        let argument = tokens.next();
+
+        let command = match (name.as_str(), argument) {
+            ("ABOUT", None) => Command::About,
+            ("START", Some(size)) => Command::Start(
+                size.parse()
+                    .map_err(|_| ProtocolError::Parse(format!("invalid size: {}", size)))?,
+            ),
+            ("RESTART", None) => Command::Restart,
+            ("FLIP", Some(position)) => match parse_action(position)? {
+                Action::Place(x, y) => Command::Flip(x, y),
+                _ => return Err(ProtocolError::Parse("FLIP expects a position".to_string())),
+            },
+            ("INFLUENCE", Some(position)) => match parse_action(position)? {
+                Action::Place(x, y) => Command::Influence(x, y),
+                _ => {
+                    return Err(ProtocolError::Parse(
+                        "INFLUENCE expects a position".to_string(),
+                    ))
+                }
+            },
+            ("CERTAIN", Some(position)) => match parse_action(position)? {
+                Action::Place(x, y) => Command::Certain(x, y),
+                _ => {
+                    return Err(ProtocolError::Parse(
+                        "CERTAIN expects a position".to_string(),
+                    ))
+                }
+            },
+            ("ERASE", Some(position)) => match parse_action(position)? {
+                Action::Place(x, y) => Command::Erase(x, y),
+                _ => return Err(ProtocolError::Parse("ERASE expects a position".to_string())),
+            },
+            ("PLAY", Some(position)) => match parse_action(position)? {
+                Action::Place(x, y) => Command::Play(x, y),
+                Action::PlaceEntangled(_)
+                | Action::PlaceSuperposed(_)
+                | Action::PlaceInfluencer(..)
+                | Action::PlaceCertain(..)
+                | Action::Flip(..)
+                | Action::Erase(..)
+                | Action::Observe
+                | Action::ObserveRegion(_) => {
+                    return Err(ProtocolError::Parse("PLAY expects a position".to_string()))
+                }
+            },
+            ("OBSERVED", Some(cells)) => {
+                let board = Box::new(parse_observation(cells)?);
+                match tokens.next() {
+                    Some(region) => Command::ObservedRegion(parse_region(region)?, board),
+                    None => Command::Observed(board),
+                }
+            }
+            ("TURN", None) => Command::Turn,
+            ("END", None) => Command::End,
+            _ => return Err(ProtocolError::Parse(format!("unknown command: {}", line))),
+        };
+
+        if tokens.next().is_some() {
+            return Err(ProtocolError::Parse(format!(
+                "too many arguments: {}",
+                line
+            )));
+        }
+
+        Ok(command)
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::About => write!(f, "ABOUT"),
+            Command::Start(size) => write!(f, "START {}", size),
+            Command::Restart => write!(f, "RESTART"),
+            Command::Rules(rules) => write!(f, "RULES {}", format_rules(rules)),
+            Command::Setup(position) => write!(f, "SETUP {}", format_setup(position)),
+            Command::Play(x, y) => write!(f, "PLAY {}", format_action(Action::Place(*x, *y))),
+            Command::Entangle(entanglement) => {
+                f.write_str(&format_action(Action::PlaceEntangled(*entanglement)))
+            }
+            Command::Superpose(cells) => {
+                f.write_str(&format_action(Action::PlaceSuperposed(*cells)))
+            }
+            Command::Flip(x, y) => f.write_str(&format_action(Action::Flip(*x, *y))),
+            Command::Influence(x, y) => {
+                f.write_str(&format_action(Action::PlaceInfluencer(*x, *y)))
+            }
+            Command::Certain(x, y) => f.write_str(&format_action(Action::PlaceCertain(*x, *y))),
+            Command::Erase(x, y) => f.write_str(&format_action(Action::Erase(*x, *y))),
+            Command::Observed(board) => write!(f, "OBSERVED {}", format_observation(board)),
+            Command::ObservedRegion(region, board) => write!(
+                f,
+                "OBSERVED {} {}",
+                format_observation(board),
+                format_region(*region)
+            ),
+            Command::Turn => write!(f, "TURN"),
+            Command::End => write!(f, "END"),
+        }
+    }
+}
+
+pub fn format_action(action: Action) -> String {
+    match action {
+        Action::Place(x, y) => format!("{},{}", x, y),
+        Action::PlaceEntangled(Entanglement {
+            first: (x1, y1),
+            second: (x2, y2),
+            correlation,
+        }) => format!(
+            "ENTANGLE {},{} {},{} {}",
+            x1,
+            y1,
+            x2,
+            y2,
+            match correlation {
+                Correlation::Correlated => "correlated",
+                Correlation::Anticorrelated => "anticorrelated",
+            }
+        ),
+        Action::PlaceSuperposed([(x1, y1), (x2, y2)]) => {
+            format!("SUPERPOSE {},{} {},{}", x1, y1, x2, y2)
+        }
+        Action::Flip(x, y) => format!("FLIP {},{}", x, y),
+        Action::PlaceInfluencer(x, y) => format!("INFLUENCE {},{}", x, y),
+        Action::PlaceCertain(x, y) => format!("CERTAIN {},{}", x, y),
+        Action::Erase(x, y) => format!("ERASE {},{}", x, y),
+        Action::Observe => "OBSERVE".to_string(),
+        Action::ObserveRegion(region) => format!("OBSERVE {}", format_region(region)),
+    }
+}
+
+pub fn parse_action(text: &str) -> Result<Action, ProtocolError> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("OBSERVE") {
+        return Ok(Action::Observe);
+    }
+
+    let invalid = || ProtocolError::Parse(format!("invalid action: {}", text));
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    match tokens.as_slice() {
+        [name, first, second, correlation] if name.eq_ignore_ascii_case("ENTANGLE") => {
+            let correlation = match correlation.to_ascii_lowercase().as_str() {
+                "correlated" => Correlation::Correlated,
+                "anticorrelated" => Correlation::Anticorrelated,
+                _ => return Err(invalid()),
+            };
+            Ok(Action::PlaceEntangled(Entanglement {
+                first: parse_position(first).ok_or_else(invalid)?,
+                second: parse_position(second).ok_or_else(invalid)?,
+                correlation,
+            }))
+        }
+        [name, region] if name.eq_ignore_ascii_case("OBSERVE") => {
+            Ok(Action::ObserveRegion(parse_region(region)?))
+        }
+        [name, position] if name.eq_ignore_ascii_case("FLIP") => {
+            let (x, y) = parse_position(position).ok_or_else(invalid)?;
+            Ok(Action::Flip(x, y))
+        }
+        [name, position] if name.eq_ignore_ascii_case("INFLUENCE") => {
+            let (x, y) = parse_position(position).ok_or_else(invalid)?;
+            Ok(Action::PlaceInfluencer(x, y))
+        }
+        [name, position] if name.eq_ignore_ascii_case("CERTAIN") => {
+            let (x, y) = parse_position(position).ok_or_else(invalid)?;
+            Ok(Action::PlaceCertain(x, y))
+        }
+        [name, position] if name.eq_ignore_ascii_case("ERASE") => {
+            let (x, y) = parse_position(position).ok_or_else(invalid)?;
+            Ok(Action::Erase(x, y))
+        }
+        [name, first, second] if name.eq_ignore_ascii_case("SUPERPOSE") => {
+            Ok(Action::PlaceSuperposed([
+                parse_position(first).ok_or_else(invalid)?,
+                parse_position(second).ok_or_else(invalid)?,
+            ]))
+        }
+        _ => {
+            let (x, y) = parse_position(text).ok_or_else(invalid)?;
+            Ok(Action::Place(x, y))
+        }
+    }
+}
+
+fn parse_position(text: &str) -> Option<(usize, usize)> {
+    let (x, y) = text.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Every rule as a `<name>=<value>` token:
+/// `observe=passes|keeps`, `black-first=strong|weak`, `white-first=strong|weak`,
+/// `tie-break=observer|opponent|continue`, `observation-limit=none|<n>`,
+/// `entanglement=on|off`, `superposition=on|off`, `model=classical|amplitude`,
+/// `observation=board|region`, `collapse=resample|persistent`,
+/// `decoherence=none|even:<step>|certain:<step>`, `influence=none|<n>`,
+/// `certain-stones=<n>`, `erasure=none|<n>`, `teams=on|off`,
+/// `topology=bounded|toroidal`, `grid=square|hex`, `gravity=on|off` and
+/// `handicap=none|<black|white>:<n>:<certain|strong>`.
+/// Positions stay `<x>,<y>` on the hex grid too, x and y being the axial q and
+/// r of the cell. Under gravity a position must be the lowest free cell of its
+/// column.
+pub fn format_rules(rules: &Rules) -> String {
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    let stone = |stone: PColor| match stone {
+        PColor::Strong => "strong",
+        PColor::Weak => "weak",
+        PColor::Certain => "certain",
+    };
+
+    [
+        format!(
+            "observe={}",
+            if rules.observe_passes_turn {
+                "passes"
+            } else {
+                "keeps"
+            }
+        ),
+        format!("black-first={}", stone(rules.black_first_stone)),
+        format!("white-first={}", stone(rules.white_first_stone)),
+        format!(
+            "tie-break={}",
+            match rules.tie_break {
+                TieBreak::Observer => "observer",
+                TieBreak::Opponent => "opponent",
+                TieBreak::Continue => "continue",
+            }
+        ),
+        match rules.observation_limit {
+            Some(limit) => format!("observation-limit={}", limit),
+            None => "observation-limit=none".to_string(),
+        },
+        format!("entanglement={}", on_off(rules.allow_entanglement)),
+        format!("superposition={}", on_off(rules.allow_superposition)),
+        format!(
+            "model={}",
+            match rules.stone_model {
+                StoneModelKind::Classical => "classical",
+                StoneModelKind::Amplitude => "amplitude",
+            }
+        ),
+        format!(
+            "observation={}",
+            if rules.regional_observation {
+                "region"
+            } else {
+                "board"
+            }
+        ),
+        format!(
+            "collapse={}",
+            if rules.persistent_collapse {
+                "persistent"
+            } else {
+                "resample"
+            }
+        ),
+        match rules.decoherence {
+            Some(decoherence) => format!(
+                "decoherence={}:{}",
+                match decoherence.toward {
+                    Drift::Even => "even",
+                    Drift::Certain => "certain",
+                },
+                decoherence.step
+            ),
+            None => "decoherence=none".to_string(),
+        },
+        match rules.influence {
+            Some(influence) => format!("influence={}", influence),
+            None => "influence=none".to_string(),
+        },
+        format!("certain-stones={}", rules.certain_stones),
+        match rules.erasure {
+            Some(threshold) => format!("erasure={}", threshold),
+            None => "erasure=none".to_string(),
+        },
+        format!("teams={}", on_off(rules.teams)),
+        format!(
+            "topology={}",
+            match rules.topology {
+                Topology::Bounded => "bounded",
+                Topology::Toroidal => "toroidal",
+            }
+        ),
+        format!(
+            "grid={}",
+            match rules.grid {
+                Grid::Square => "square",
+                Grid::Hex => "hex",
+            }
+        ),
+        format!("gravity={}", on_off(rules.gravity)),
+        match rules.handicap {
+            Some(handicap) => format!("handicap={}", format_handicap(handicap)),
+            None => "handicap=none".to_string(),
+        },
+    ]
+    .join(" ")
+}
+
+pub fn parse_decoherence(text: &str) -> Option<Decoherence> {
+    let (toward, step) = text.split_once(':')?;
+    let toward = match toward {
+        "even" => Drift::Even,
+        "certain" => Drift::Certain,
+        _ => return None,
+    };
+    Some(Decoherence {
+        toward,
+        step: step.parse().ok()?,
+    })
+}
+
+pub fn format_handicap(handicap: Handicap) -> String {
+    format!(
+        "{}:{}:{}",
+        handicap.player,
+        handicap.stones,
+        match handicap.stone {
+            PColor::Certain => "certain",
+            PColor::Strong | PColor::Weak => "strong",
+        }
+    )
+}
+
+pub fn parse_handicap(text: &str) -> Option<Handicap> {
+    let mut parts = text.split(':');
+    let player = match parts.next()? {
+        "black" => PlayerKind::Black,
+        "white" => PlayerKind::White,
+        _ => return None,
+    };
+    let stones = parts.next()?.parse().ok()?;
+    let stone = match parts.next()? {
+        "certain" => PColor::Certain,
+        "strong" => PColor::Strong,
+        _ => return None,
+    };
+    if parts.next().is_some() || !(1..=MAX_HANDICAP).contains(&stones) {
+        return None;
+    }
+
+    Some(Handicap {
+        player,
+        stones,
+        stone,
+    })
+}
+
+pub fn parse_rules(text: &str) -> Result<Rules, ProtocolError> {
+    let mut rules = Rules::default();
+
+    for token in text.split_whitespace() {
+        let invalid = || ProtocolError::Parse(format!("invalid rule: {}", token));
+        let (name, value) = token.split_once('=').ok_or_else(invalid)?;
+        let on_off = || match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(invalid()),
+        };
+        let stone = || match value {
+            "strong" => Ok(PColor::Strong),
+            "weak" => Ok(PColor::Weak),
+            _ => Err(invalid()),
+        };
+
+        match name {
+            "observe" => {
+                rules.observe_passes_turn = match value {
+                    "passes" => true,
+                    "keeps" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "black-first" => rules.black_first_stone = stone()?,
+            "white-first" => rules.white_first_stone = stone()?,
+            "tie-break" => {
+                rules.tie_break = match value {
+                    "observer" => TieBreak::Observer,
+                    "opponent" => TieBreak::Opponent,
+                    "continue" => TieBreak::Continue,
+                    _ => return Err(invalid()),
+                }
+            }
+            "observation-limit" => {
+                rules.observation_limit = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            "entanglement" => rules.allow_entanglement = on_off()?,
+            "superposition" => rules.allow_superposition = on_off()?,
+            "teams" => rules.teams = on_off()?,
+            "gravity" => rules.gravity = on_off()?,
+            "topology" => {
+                rules.topology = match value {
+                    "bounded" => Topology::Bounded,
+                    "toroidal" => Topology::Toroidal,
+                    _ => return Err(invalid()),
+                }
+            }
+            "grid" => {
+                rules.grid = match value {
+                    "square" => Grid::Square,
+                    "hex" => Grid::Hex,
+                    _ => return Err(invalid()),
+                }
+            }
+            "model" => {
+                rules.stone_model = match value {
+                    "classical" => StoneModelKind::Classical,
+                    "amplitude" => StoneModelKind::Amplitude,
+                    _ => return Err(invalid()),
+                }
+            }
+            "observation" => {
+                rules.regional_observation = match value {
+                    "board" => false,
+                    "region" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            "collapse" => {
+                rules.persistent_collapse = match value {
+                    "resample" => false,
+                    "persistent" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            "decoherence" => {
+                rules.decoherence = match value {
+                    "none" => None,
+                    _ => Some(parse_decoherence(value).ok_or_else(invalid)?),
+                }
+            }
+            "handicap" => {
+                rules.handicap = match value {
+                    "none" => None,
+                    _ => Some(parse_handicap(value).ok_or_else(invalid)?),
+                }
+            }
+            "influence" => {
+                rules.influence = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            "certain-stones" => rules.certain_stones = value.parse().map_err(|_| invalid())?,
+            "erasure" => {
+                rules.erasure = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    rules
+        .validate()
+        .map_err(|err| ProtocolError::Parse(err.to_string()))?;
+
+    Ok(rules)
+}
+
+/// `window:<x>,<y>` for the 5x5 window centred on a cell, `row:<y>`,
+/// `column:<x>`, and `diagonal:<x>,<y>` / `antidiagonal:<x>,<y>` for the
+/// diagonal running down-right / up-right through a cell. On the hex grid the
+/// window is the hexagon of cells within two steps, and there is no diagonal.
+pub fn format_region(region: Region) -> String {
+    match region {
+        Region::Window(x, y) => format!("window:{},{}", x, y),
+        Region::Row(y) => format!("row:{}", y),
+        Region::Column(x) => format!("column:{}", x),
+        Region::Diagonal(x, y) => format!("diagonal:{},{}", x, y),
+        Region::AntiDiagonal(x, y) => format!("antidiagonal:{},{}", x, y),
+    }
+}
+
+pub fn parse_region(text: &str) -> Result<Region, ProtocolError> {
+    let invalid = || ProtocolError::Parse(format!("invalid region: {}", text));
+    let (kind, anchor) = text.split_once(':').ok_or_else(invalid)?;
+    let line = || anchor.parse().map_err(|_| invalid());
+    let cell = || parse_position(anchor).ok_or_else(invalid);
+
+    match kind.to_ascii_lowercase().as_str() {
+        "window" => cell().map(|(x, y)| Region::Window(x, y)),
+        "row" => line().map(Region::Row),
+        "column" => line().map(Region::Column),
+        "diagonal" => cell().map(|(x, y)| Region::Diagonal(x, y)),
+        "antidiagonal" => cell().map(|(x, y)| Region::AntiDiagonal(x, y)),
+        _ => Err(invalid()),
+    }
+}
+
+pub fn format_observation(board: &ObservedBoard) -> String {
+    board
+        .iter()
+        .flatten()
+        .map(|cell| match cell {
+            None => '.',
+            Some(PlayerKind::Black) => 'x',
+            Some(PlayerKind::White) => 'o',
+        })
+        .collect()
+}
+
+pub fn format_setup(position: &Position) -> String {
+    let stone = |stone: PColor| match stone {
+        PColor::Strong => "strong",
+        PColor::Weak | PColor::Certain => "weak",
+    };
+    let cells: String = (0..BOARD_SIZE)
+        .flat_map(|y| (0..BOARD_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| match position.stone(x, y) {
+            None => '.',
+            Some(Color::Black(PColor::Strong)) => 'B',
+            Some(Color::Black(PColor::Weak)) => 'b',
+            Some(Color::Black(PColor::Certain)) => 'X',
+            Some(Color::White(PColor::Strong)) => 'W',
+            Some(Color::White(PColor::Weak)) => 'w',
+            Some(Color::White(PColor::Certain)) => 'O',
+        })
+        .collect();
+
+    format!(
+        "{} {} {} {}",
+        position.turn(),
+        stone(position.next_stone(PlayerKind::Black)),
+        stone(position.next_stone(PlayerKind::White)),
+        cells
+    )
+}
+
+pub fn parse_setup(text: &str) -> Result<Position, ProtocolError> {
+    let invalid = || ProtocolError::Parse(format!("invalid setup: {}", text));
+    let [turn, black, white, cells] = text.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    let stone = |text: &str| match text {
+        "strong" => Ok(PColor::Strong),
+        "weak" => Ok(PColor::Weak),
+        _ => Err(invalid()),
+    };
+    if cells.chars().count() != BOARD_SIZE * BOARD_SIZE {
+        return Err(invalid());
+    }
+
+    let mut position = Position::new(&Rules::default());
+    position.set_turn(match turn {
+        "black" => PlayerKind::Black,
+        "white" => PlayerKind::White,
+        _ => return Err(invalid()),
+    });
+    position.set_next_stone(PlayerKind::Black, stone(black)?);
+    position.set_next_stone(PlayerKind::White, stone(white)?);
+    for (i, c) in cells.chars().enumerate() {
+        let color = match c {
+            '.' => None,
+            'B' => Some(Color::Black(PColor::Strong)),
+            'b' => Some(Color::Black(PColor::Weak)),
+            'X' => Some(Color::Black(PColor::Certain)),
+            'W' => Some(Color::White(PColor::Strong)),
+            'w' => Some(Color::White(PColor::Weak)),
+            'O' => Some(Color::White(PColor::Certain)),
+            _ => return Err(ProtocolError::Parse(format!("invalid cell: {}", c))),
+        };
+        position
+            .set_stone(i % BOARD_SIZE, i / BOARD_SIZE, color)
+            .map_err(|err| ProtocolError::Parse(err.to_string()))?;
+    }
+
+    Ok(position)
+}
+
+pub fn parse_observation(cells: &str) -> Result<ObservedBoard, ProtocolError> {
+    if cells.chars().count() != BOARD_SIZE * BOARD_SIZE {
+        return Err(ProtocolError::Parse(format!(
+            "expected {} cells, got {}",
+            BOARD_SIZE * BOARD_SIZE,
+            cells.chars().count()
+        )));
+    }
+
+    let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+    for (i, c) in cells.chars().enumerate() {
+        board[i / BOARD_SIZE][i % BOARD_SIZE] = match c {
+            '.' => None,
+            'x' => Some(PlayerKind::Black),
+            'o' => Some(PlayerKind::White),
+            _ => return Err(ProtocolError::Parse(format!("invalid cell: {}", c))),
+        };
+    }
+
+    Ok(board)
+}
+
+pub enum Reply {
+    Line(String),
+    Quit,
+}
+
+pub struct EngineAdapter<A: Agent> {
+    game: Game,
+    agent: A,
+}
+
+impl<A: Agent> EngineAdapter<A> {
+    pub fn new(agent: A) -> EngineAdapter<A> {
+        EngineAdapter {
+            game: Game::new(),
+            agent,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn handle(&mut self, line: &str) -> Reply {
+        match line.parse() {
+            Ok(command) => self.execute(command),
+            Err(err) => Reply::Line(format!("ERROR {}", err)),
+        }
+    }
+
+    fn execute(&mut self, command: Command) -> Reply {
+        let result = match command {
+            Command::About => Ok(format!(
+                "name=\"{}\", version=\"{}\"",
+                self.agent.name(),
+                env!("CARGO_PKG_VERSION")
+            )),
+            Command::Start(size) if size != BOARD_SIZE => {
+                Err(format!("unsupported size: {}", size))
+            }
+            Command::Start(_) | Command::Restart => {
+                self.game.reset();
+                Ok("OK".to_string())
+            }
+            Command::Rules(rules) => {
+                self.game = Game::with_rules(rules);
+                Ok("OK".to_string())
+            }
+            Command::Setup(position) => Game::from_position(*self.game.rules(), &position)
+                .map(|game| {
+                    self.game = game;
+                    "OK".to_string()
+                })
+                .map_err(|err| err.to_string()),
+            Command::Play(x, y) => self
+                .game
+                .place_stone_prohibitly(x, y)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Entangle(entanglement) => self
+                .game
+                .place_entangled(entanglement)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Superpose(cells) => self
+                .game
+                .place_superposed(cells)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Flip(x, y) => self
+                .game
+                .flip(x, y)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Influence(x, y) => self
+                .game
+                .place_influencer(x, y)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Certain(x, y) => self
+                .game
+                .place_certain(x, y)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Erase(x, y) => self
+                .game
+                .erase(x, y)
+                .map(|_| "OK".to_string())
+                .map_err(|err| err.to_string()),
+            Command::Observed(board) => self.observed(*board, None),
+            Command::ObservedRegion(region, board) => self.observed(*board, Some(region)),
+            Command::Turn => self
+                .agent
+                .select_action(&self.game)
+                .map(format_action)
+                .map_err(|err| err.to_string()),
+            Command::End => return Reply::Quit,
+        };
+
+        Reply::Line(result.unwrap_or_else(|err| format!("ERROR {}", err)))
+    }
+
+    fn observed(&mut self, board: ObservedBoard, region: Option<Region>) -> Result<String, String> {
+        self.game
+            .check_observation(region)
+            .map_err(|err| err.to_string())?;
+
+        let mut superposed = 0;
+        let mut matches = true;
+        for (y, row) in board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if region.is_some_and(|region| !region.contains(x, y, self.game.rules().grid)) {
+                    matches &= *cell == self.game.observed().get(x, y);
+                    continue;
+                }
+                match self.game.cell(x, y) {
+                    Cell::Empty => matches &= cell.is_none(),
+                    Cell::Stone(_) => matches &= cell.is_some(),
+                    Cell::Superposed(_) => superposed += usize::from(cell.is_some()),
+                }
+            }
+        }
+        // Every superposed stone must have been seen on a cell of its own.
+        if region.is_none() {
+            matches &= superposed == self.game.superpositions().len()
+                && superposition::arrangement_within(self.game.superpositions(), |(x, y)| {
+                    board[y][x].is_some() && self.game.stone(x, y).is_none()
+                })
+                .is_some();
+        }
+        if !matches {
+            return Err(GameError::InvalidObservation.to_string());
+        }
+
+        match region {
+            Some(region) => self.game.apply_region_observation(region, board),
+            None => self.game.apply_observation(board),
+        }
+        .map(|_| "OK".to_string())
+        .map_err(|err| err.to_string())
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match self.handle(&line) {
+                Reply::Line(reply) => {
+                    writeln!(output, "{}", reply)?;
+                    output.flush()?;
+                }
+                Reply::Quit => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ExternalEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    name: String,
+    rules: Option<Rules>,
+    start: Option<Position>,
+    synced: usize,
+}
+
+impl ExternalEngine {
+    pub fn spawn(command_line: &str) -> Result<ExternalEngine, ProtocolError> {
+        let mut parts = command_line.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| ProtocolError::Parse("empty engine command".to_string()))?;
+
+        let mut child = Process::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| ProtocolError::Io(err.to_string()))?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        let mut engine = ExternalEngine {
+            child,
+            stdin,
+            stdout,
+            name: program.to_string(),
+            rules: None,
+            start: None,
+            synced: 0,
+        };
+
+        engine.expect_ok(&Command::Start(BOARD_SIZE))?;
+        let about = engine.request(&Command::About)?;
+        if let Some(name) = parse_about_name(&about) {
+            engine.name = name;
+        }
+
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn restart(&mut self) -> Result<(), ProtocolError> {
+        self.expect_ok(&Command::Restart)?;
+        self.synced = 0;
+        Ok(())
+    }
+
+    pub fn sync(&mut self, game: &Game) -> Result<(), ProtocolError> {
+        if self.rules != Some(*game.rules()) || self.start.as_ref() != game.start_position() {
+            self.expect_ok(&Command::Rules(*game.rules()))?;
+            if let Some(position) = game.start_position() {
+                self.expect_ok(&Command::Setup(Box::new(*position)))?;
+            }
+            self.rules = Some(*game.rules());
+            self.start = game.start_position().copied();
+            self.synced = 0;
+        }
+
+        let history = game.history();
+        if history.len() < self.synced {
+            self.restart()?;
+        }
+
+        for mv in &history[self.synced..] {
+            let command = match mv {
+                Move::Place {
+                    x,
+                    y,
+                    color: Color::Black(PColor::Certain) | Color::White(PColor::Certain),
+                } => Command::Certain(*x, *y),
+                Move::Place { x, y, .. } => Command::Play(*x, *y),
+                Move::PlaceEntangled { entanglement, .. } => Command::Entangle(*entanglement),
+                Move::PlaceSuperposed(superposition) => Command::Superpose(superposition.cells),
+                Move::Flip { x, y } => Command::Flip(*x, *y),
+                Move::PlaceInfluencer { x, y, .. } => Command::Influence(*x, *y),
+                Move::Erase { x, y, .. } => Command::Erase(*x, *y),
+                Move::Observe(board) => Command::Observed(board.clone()),
+                Move::ObserveRegion { region, board } => {
+                    Command::ObservedRegion(*region, board.clone())
+                }
+            };
+            self.expect_ok(&command)?;
+            self.synced += 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn turn(&mut self) -> Result<Action, ProtocolError> {
+        let reply = self.request(&Command::Turn)?;
+        parse_action(&reply)
+    }
+
+    fn request(&mut self, command: &Command) -> Result<String, ProtocolError> {
+        writeln!(self.stdin, "{}", command).map_err(|err| ProtocolError::Io(err.to_string()))?;
+        self.stdin
+            .flush()
+            .map_err(|err| ProtocolError::Io(err.to_string()))?;
+
+        let mut reply = String::new();
+        let read = self
+            .stdout
+            .read_line(&mut reply)
+            .map_err(|err| ProtocolError::Io(err.to_string()))?;
+        if read == 0 {
+            return Err(ProtocolError::Disconnected);
+        }
+
+        let reply = reply.trim();
+        match reply.strip_prefix("ERROR") {
+            Some(message) => Err(ProtocolError::Engine(message.trim().to_string())),
+            None => Ok(reply.to_string()),
+        }
+    }
+
+    fn expect_ok(&mut self, command: &Command) -> Result<(), ProtocolError> {
+        let reply = self.request(command)?;
+        if reply == "OK" {
+            Ok(())
+        } else {
+            Err(ProtocolError::UnexpectedReply(reply))
+        }
+    }
+}
+
+fn parse_about_name(about: &str) -> Option<String> {
+    let start = about.find("name=\"")? + "name=\"".len();
+    let end = about[start..].find('"')? + start;
+    Some(about[start..end].to_string())
+}
+
+impl Agent for ExternalEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), AgentError> {
+        self.restart()
+            .map_err(|err| AgentError::Engine(err.to_string()))
+    }
+
+    fn select_action(&mut self, game: &Game) -> Result<Action, AgentError> {
+        self.sync(game)
+            .and_then(|_| self.turn())
+            .map_err(|err| AgentError::Engine(err.to_string()))
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "{}", Command::End);
+        let _ = self.stdin.flush();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(String),
+    Parse(String),
+    Engine(String),
+    UnexpectedReply(String),
+    Disconnected,
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Io(message) => write!(f, "I/O error: {}", message),
+            ProtocolError::Parse(message) => write!(f, "Parse error: {}", message),
+            ProtocolError::Engine(message) => write!(f, "Engine error: {}", message),
+            ProtocolError::UnexpectedReply(reply) => write!(f, "Unexpected reply: {}", reply),
+            ProtocolError::Disconnected => write!(f, "Engine disconnected"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::RandomAgent;
+
+    fn reply<A: Agent>(engine: &mut EngineAdapter<A>, line: &str) -> String {
+        match engine.handle(line) {
+            Reply::Line(reply) => reply,
+            Reply::Quit => "QUIT".to_string(),
+        }
+    }
+
+    #[test]
+    fn observed_rejects_a_board_the_superposed_stones_cannot_settle_on() {
+        let mut engine = EngineAdapter::new(RandomAgent::new(0));
+        for line in [
+            "RULES superposition=on collapse=persistent",
+            "SUPERPOSE 0,0 1,0",
+            "PLAY 5,5",
+            "SUPERPOSE 0,1 1,1",
+            "PLAY 6,6",
+        ] {
+            assert_eq!(reply(&mut engine, line), "OK");
+        }
+
+        // As many superposed cells as stones, but both on the first stone.
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        board[0][0] = Some(PlayerKind::Black);
+        board[0][1] = Some(PlayerKind::Black);
+        board[5][5] = Some(PlayerKind::Black);
+        board[6][6] = Some(PlayerKind::White);
+        let hash = engine.game().hash();
+
+        let line = format!("OBSERVED {}", format_observation(&board));
+        assert!(reply(&mut engine, &line).starts_with("ERROR"));
+        assert_eq!(engine.game().hash(), hash);
+        assert_eq!(engine.game().superpositions().len(), 2);
+
+        board[0][1] = None;
+        board[1][1] = Some(PlayerKind::White);
+        let line = format!("OBSERVED {}", format_observation(&board));
+        assert_eq!(reply(&mut engine, &line), "OK");
+        assert!(engine.game().superpositions().is_empty());
+    }
+
+    #[test]
+    fn observed_follows_the_observation_rules() {
+        let empty = format_observation(&[[None; BOARD_SIZE]; BOARD_SIZE]);
+
+        let mut engine = EngineAdapter::new(RandomAgent::new(0));
+        assert_eq!(reply(&mut engine, "RULES observation=region"), "OK");
+        let line = format!("OBSERVED {}", empty);
+        assert!(reply(&mut engine, &line).starts_with("ERROR"));
+        let line = format!("OBSERVED {} row:3", empty);
+        assert_eq!(reply(&mut engine, &line), "OK");
+
+        let mut engine = EngineAdapter::new(RandomAgent::new(0));
+        assert_eq!(reply(&mut engine, "RULES observation-limit=1"), "OK");
+        let line = format!("OBSERVED {} row:3", empty);
+        assert!(reply(&mut engine, &line).starts_with("ERROR"));
+        for _ in 0..2 {
+            assert_eq!(reply(&mut engine, &format!("OBSERVED {}", empty)), "OK");
+        }
+        assert!(reply(&mut engine, &format!("OBSERVED {}", empty)).starts_with("ERROR"));
+        assert_eq!(engine.game().history().len(), 2);
+    }
+
+    #[test]
+    fn rules_round_trip_through_every_option() {
+        let options: [(&str, &[&str]); 19] = [
+            ("observe", &["passes", "keeps"]),
+            ("black-first", &["strong", "weak"]),
+            ("white-first", &["strong", "weak"]),
+            ("tie-break", &["observer", "opponent", "continue"]),
+            ("observation-limit", &["none", "0", "7"]),
+            ("entanglement", &["on", "off"]),
+            ("superposition", &["on", "off"]),
+            ("model", &["classical", "amplitude"]),
+            ("observation", &["board", "region"]),
+            ("collapse", &["resample", "persistent"]),
+            ("decoherence", &["none", "even:5", "certain:12"]),
+            ("influence", &["none", "20"]),
+            ("certain-stones", &["0", "3"]),
+            ("erasure", &["none", "60"]),
+            ("teams", &["on", "off"]),
+            ("topology", &["bounded", "toroidal"]),
+            ("grid", &["square", "hex"]),
+            ("gravity", &["on", "off"]),
+            ("handicap", &["none", "black:1:certain", "white:9:strong"]),
+        ];
+
+        for (name, values) in options {
+            for value in values {
+                let token = format!("{}={}", name, value);
+                let rules = parse_rules(&token).unwrap();
+                let text = format_rules(&rules);
+                assert!(text.split(' ').any(|part| part == token), "{}", text);
+                assert_eq!(parse_rules(&text).unwrap(), rules);
+            }
+        }
+
+        let rules = Rules::default();
+        assert_eq!(parse_rules(&format_rules(&rules)).unwrap(), rules);
+        assert!(parse_rules("handicap=black:10:certain").is_err());
+        assert!(parse_rules("gravity=on erasure=60").is_err());
+    }
+
+    #[test]
+    fn flip_off_the_board_is_an_error() {
+        let mut engine = EngineAdapter::new(RandomAgent::new(0));
+        for line in ["RULES model=amplitude", "PLAY 0,0"] {
+            assert_eq!(reply(&mut engine, line), "OK");
+        }
+
+        assert!(reply(&mut engine, "FLIP 100,100").starts_with("ERROR"));
+        assert!(reply(&mut engine, "FLIP 0,18").starts_with("ERROR"));
+        assert_eq!(engine.game().history().len(), 1);
+    }
+}
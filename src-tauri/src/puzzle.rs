@@ -0,0 +1,322 @@
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::Path};
+
+use rand::Rng;
+
+use crate::{
+    analysis::observation_outcome,
+    game::{Action, Game, GameError, Rules},
+    player::{Color, PColor, PlayerKind},
+    position::Position,
+};
+
+// Enough samples to tell the bundled puzzles' answers apart from the
+// nearest wrong ones.
+pub const SAMPLES: usize = 20_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Goal {
+    // After the move, an observation wins for the mover at least this often.
+    Win { percent: u8 },
+    // After the move, an observation wins for the opponent at most this often.
+    Block { percent: u8 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Verdict {
+    pub solved: bool,
+    // The mover's chance under `Goal::Win`, the opponent's under `Goal::Block`.
+    pub probability: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub rules: Rules,
+    pub position: Position,
+    pub goal: Goal,
+}
+
+impl Puzzle {
+    pub fn game(&self) -> Game {
+        Game::from_position(self.rules, &self.position).expect("bundled puzzles are valid")
+    }
+
+    pub fn check<R: Rng + ?Sized>(
+        &self,
+        answer: Action,
+        samples: usize,
+        rng: &mut R,
+    ) -> Result<Verdict, GameError> {
+        if matches!(answer, Action::Observe | Action::ObserveRegion(_)) {
+            return Err(GameError::ObservationNotAnAnswer);
+        }
+
+        let mut game = self.game();
+        let mover = game.turn();
+        game.apply_action_with(answer, rng)?;
+
+        let outcome = observation_outcome(&game, samples, rng);
+        Ok(match self.goal {
+            Goal::Win { percent } => Verdict {
+                solved: outcome.of(mover) * 100.0 >= percent as f64,
+                probability: outcome.of(mover),
+            },
+            Goal::Block { percent } => Verdict {
+                solved: outcome.of(mover.opponent()) * 100.0 <= percent as f64,
+                probability: outcome.of(mover.opponent()),
+            },
+        })
+    }
+}
+
+fn puzzle(
+    id: &'static str,
+    title: &'static str,
+    rules: Rules,
+    turn: PlayerKind,
+    next_stone: PColor,
+    stones: &[(usize, usize, u8)],
+    goal: Goal,
+) -> Puzzle {
+    let mut position = Position::new(&rules);
+    position.set_turn(turn);
+    position.set_next_stone(turn, next_stone);
+    for &(x, y, p) in stones {
+        let color = Color::from_p(p).expect("stones are written as stone probabilities");
+        position
+            .set_stone(x, y, Some(color))
+            .expect("stones are on the board");
+    }
+
+    Puzzle {
+        id,
+        title,
+        rules,
+        position,
+        goal,
+    }
+}
+
+pub fn puzzles() -> Vec<Puzzle> {
+    vec![
+        puzzle(
+            "last-stone",
+            "五つ目の石",
+            Rules::default(),
+            PlayerKind::Black,
+            PColor::Strong,
+            &[
+                (5, 8, 0),
+                (6, 8, 100),
+                (7, 8, 100),
+                (8, 8, 100),
+                (9, 8, 100),
+            ],
+            Goal::Win { percent: 80 },
+        ),
+        puzzle(
+            "which-line",
+            "どちらの列",
+            Rules::default(),
+            PlayerKind::Black,
+            PColor::Strong,
+            &[
+                (3, 4, 70),
+                (4, 4, 70),
+                (5, 4, 70),
+                (6, 4, 70),
+                (10, 12, 90),
+                (11, 12, 90),
+                (12, 12, 90),
+                (13, 12, 100),
+            ],
+            Goal::Win { percent: 60 },
+        ),
+        puzzle(
+            "certain-stone",
+            "確定した石の使いどころ",
+            Rules {
+                certain_stones: 1,
+                ..Rules::default()
+            },
+            PlayerKind::Black,
+            PColor::Weak,
+            &[(7, 7, 90), (8, 8, 90), (9, 9, 90), (10, 10, 90)],
+            Goal::Win { percent: 60 },
+        ),
+        puzzle(
+            "erase",
+            "相手の石を消す",
+            Rules {
+                erasure: Some(80),
+                ..Rules::default()
+            },
+            PlayerKind::Black,
+            PColor::Strong,
+            &[
+                (4, 14, 0),
+                (5, 14, 0),
+                (6, 14, 0),
+                (7, 14, 0),
+                (8, 14, 30),
+                (12, 3, 30),
+                (13, 4, 10),
+            ],
+            Goal::Block { percent: 10 },
+        ),
+        puzzle(
+            "crossing",
+            "交点",
+            Rules::default(),
+            PlayerKind::White,
+            PColor::Strong,
+            &[
+                (3, 16, 10),
+                (4, 16, 10),
+                (5, 16, 10),
+                (6, 16, 10),
+                (7, 12, 10),
+                (7, 13, 10),
+                (7, 14, 10),
+                (7, 15, 10),
+            ],
+            Goal::Win { percent: 75 },
+        ),
+    ]
+}
+
+pub fn find(id: &str) -> Option<Puzzle> {
+    puzzles().into_iter().find(|puzzle| puzzle.id == id)
+}
+
+// Attempts and whether each puzzle has been solved, kept by puzzle id.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Progress {
+    entries: BTreeMap<String, (u32, bool)>,
+}
+
+impl Progress {
+    pub fn record(&mut self, id: &str, solved: bool) {
+        let entry = self.entries.entry(id.to_string()).or_default();
+        entry.0 += 1;
+        entry.1 |= solved;
+    }
+
+    pub fn attempts(&self, id: &str) -> u32 {
+        self.entries.get(id).map_or(0, |&(attempts, _)| attempts)
+    }
+
+    pub fn is_solved(&self, id: &str) -> bool {
+        self.entries.get(id).is_some_and(|&(_, solved)| solved)
+    }
+
+    pub fn solved_count(&self) -> usize {
+        self.entries.values().filter(|&&(_, solved)| solved).count()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Progress> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Progress::default()),
+            Err(err) => return Err(err),
+        };
+
+        let mut progress = Progress::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+            let [id, attempts, solved] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(invalid());
+            };
+            let attempts = attempts.parse().map_err(|_| invalid())?;
+            let solved = match solved {
+                "solved" => true,
+                "unsolved" => false,
+                _ => return Err(invalid()),
+            };
+            progress.entries.insert(id.to_string(), (attempts, solved));
+        }
+
+        Ok(progress)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (id, &(attempts, solved)) in &self.entries {
+            let solved = if solved { "solved" } else { "unsolved" };
+            writeln!(f, "{} {} {}", id, attempts, solved)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_take_their_answer_and_turn_down_a_wrong_move() {
+        let answers = [
+            ("last-stone", Action::Place(10, 8), Action::Place(0, 0)),
+            ("which-line", Action::Place(14, 12), Action::Place(7, 4)),
+            (
+                "certain-stone",
+                Action::PlaceCertain(11, 11),
+                Action::Place(11, 11),
+            ),
+            ("erase", Action::Erase(8, 14), Action::Erase(12, 3)),
+            ("crossing", Action::Place(7, 16), Action::Place(2, 16)),
+        ];
+        assert_eq!(answers.len(), puzzles().len());
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for (id, answer, wrong) in answers {
+            let puzzle = find(id).unwrap();
+            let verdict = puzzle.check(answer, SAMPLES, &mut rng).unwrap();
+            assert!(verdict.solved, "{}: {:?}", id, verdict);
+            let verdict = puzzle.check(wrong, SAMPLES, &mut rng).unwrap();
+            assert!(!verdict.solved, "{}: {:?}", id, verdict);
+        }
+
+        let puzzle = find("last-stone").unwrap();
+        assert!(puzzle.check(Action::Observe, SAMPLES, &mut rng).is_err());
+    }
+
+    #[test]
+    fn progress_survives_a_save_and_load() {
+        let dir = env::temp_dir().join(format!("quantum-gomoku-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("puzzles.txt");
+
+        let mut progress = Progress::default();
+        progress.record("erase", false);
+        progress.record("erase", true);
+        progress.record("crossing", false);
+        progress.save(&path).unwrap();
+
+        let loaded = Progress::load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, progress);
+        assert_eq!(loaded.attempts("erase"), 2);
+        assert!(loaded.is_solved("erase"));
+        assert!(!loaded.is_solved("crossing"));
+        assert_eq!(loaded.solved_count(), 1);
+    }
+
+    #[test]
+    fn a_missing_progress_file_loads_as_empty() {
+        let path = env::temp_dir().join(format!("quantum-gomoku-missing-{}", process::id()));
+
+        assert_eq!(Progress::load(path).unwrap(), Progress::default());
+    }
+}
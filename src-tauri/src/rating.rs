@@ -0,0 +1,140 @@
+use crate::{player::PlayerKind, record::Outcome};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchResult {
+    pub black: usize,
+    pub white: usize,
+    pub outcome: Outcome,
+}
+
+impl MatchResult {
+    pub fn score(&self, player: usize) -> Option<f64> {
+        let color = if player == self.black {
+            PlayerKind::Black
+        } else if player == self.white {
+            PlayerKind::White
+        } else {
+            return None;
+        };
+
+        Some(match self.outcome {
+            Outcome::Win(winner) if winner == color => 1.0,
+            Outcome::Win(_) => 0.0,
+            Outcome::Draw => 0.5,
+        })
+    }
+}
+
+pub fn elo(results: &[MatchResult], players: usize, k: f64) -> Vec<f64> {
+    let mut ratings = vec![0.0; players];
+
+    for result in results {
+        let expected =
+            1.0 / (1.0 + 10f64.powf((ratings[result.white] - ratings[result.black]) / 400.0));
+        let score = result.score(result.black).unwrap();
+
+        ratings[result.black] += k * (score - expected);
+        ratings[result.white] -= k * (score - expected);
+    }
+
+    ratings
+}
+
+/// Maximum a posteriori Bradley-Terry ratings, as in BayesElo: draws count as
+/// half a win for each side and every player gets two virtual draws against
+/// an average opponent so that unbeaten or winless players stay finite.
+pub fn bayes_elo(results: &[MatchResult], players: usize) -> Vec<f64> {
+    const PRIOR_GAMES: f64 = 2.0;
+    const ITERATIONS: usize = 1000;
+
+    let mut scores = vec![PRIOR_GAMES / 2.0; players];
+    let mut games = vec![vec![0.0; players]; players];
+    for result in results {
+        scores[result.black] += result.score(result.black).unwrap();
+        scores[result.white] += result.score(result.white).unwrap();
+        games[result.black][result.white] += 1.0;
+        games[result.white][result.black] += 1.0;
+    }
+
+    let mut gammas = vec![1.0; players];
+    for _ in 0..ITERATIONS {
+        let mut next = gammas.clone();
+        for i in 0..players {
+            let mut denominator = PRIOR_GAMES / (gammas[i] + 1.0);
+            for j in 0..players {
+                if games[i][j] > 0.0 {
+                    denominator += games[i][j] / (gammas[i] + gammas[j]);
+                }
+            }
+            next[i] = scores[i] / denominator;
+        }
+
+        let mean = next.iter().map(|gamma: &f64| gamma.ln()).sum::<f64>() / players.max(1) as f64;
+        for gamma in next.iter_mut() {
+            *gamma /= mean.exp();
+        }
+
+        let converged = next.iter().zip(&gammas).all(|(a, b)| (a - b).abs() < 1e-9);
+        gammas = next;
+        if converged {
+            break;
+        }
+    }
+
+    gammas.iter().map(|gamma| 400.0 * gamma.log10()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(black: usize, white: usize, outcome: Outcome) -> MatchResult {
+        MatchResult {
+            black,
+            white,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn elo_moves_ratings_by_the_surprise_of_each_result() {
+        let ratings = elo(&[result(0, 1, Outcome::Win(PlayerKind::Black))], 2, 32.0);
+        assert!((ratings[0] - 16.0).abs() < 1e-9);
+        assert!((ratings[1] + 16.0).abs() < 1e-9);
+
+        // An expected win earns less than an upset.
+        let results = [
+            result(0, 1, Outcome::Win(PlayerKind::Black)),
+            result(1, 0, Outcome::Win(PlayerKind::White)),
+        ];
+        let ratings = elo(&results, 2, 32.0);
+        assert!(ratings[0] > 16.0 && ratings[0] < 32.0);
+        assert_eq!(ratings[0], -ratings[1]);
+
+        let ratings = elo(&[result(0, 1, Outcome::Draw)], 2, 32.0);
+        assert_eq!(ratings, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn bayes_elo_orders_players_and_stays_finite() {
+        let results: Vec<MatchResult> = (0..10)
+            .flat_map(|_| {
+                [
+                    result(0, 1, Outcome::Win(PlayerKind::Black)),
+                    result(2, 1, Outcome::Win(PlayerKind::Black)),
+                    result(0, 2, Outcome::Draw),
+                    result(2, 0, Outcome::Win(PlayerKind::White)),
+                ]
+            })
+            .collect();
+        let ratings = bayes_elo(&results, 3);
+
+        assert!(ratings.iter().all(|rating| rating.is_finite()));
+        assert!(ratings[0] > ratings[2] && ratings[2] > ratings[1]);
+        // Ratings are centred on the average player.
+        assert!(ratings.iter().sum::<f64>().abs() < 1e-6);
+
+        let even = bayes_elo(&[result(0, 1, Outcome::Draw)], 2);
+        assert!(even.iter().all(|rating| rating.abs() < 1e-6));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fmt::Display, fs, io, path::Path};
+
+use crate::{
+    game::{Action, Game, Move, Rules},
+    player::PlayerKind,
+    position::Position,
+    protocol::{format_action, format_observation, format_region, format_rules, format_setup},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win(PlayerKind),
+    Draw,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win(player) => write!(f, "{}", player),
+            Outcome::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub black: String,
+    pub white: String,
+    pub seed: u64,
+    pub rules: Rules,
+    pub setup: Option<Position>,
+    pub moves: Vec<Move>,
+    pub outcome: Outcome,
+    pub note: Option<String>,
+}
+
+impl GameRecord {
+    pub fn new(black: String, white: String, seed: u64, game: &Game, outcome: Outcome) -> Self {
+        GameRecord {
+            black,
+            white,
+            seed,
+            rules: *game.rules(),
+            setup: game.start_position().copied(),
+            moves: game.history().to_vec(),
+            outcome,
+            note: None,
+        }
+    }
+
+    pub fn first_player(&self) -> PlayerKind {
+        match (self.setup, self.rules.handicap) {
+            (Some(position), _) => position.turn(),
+            (None, Some(handicap)) => handicap.player.opponent(),
+            (None, None) => PlayerKind::Black,
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[Black \"{}\"]", self.black)?;
+        writeln!(f, "[White \"{}\"]", self.white)?;
+        writeln!(f, "[Seed \"{}\"]", self.seed)?;
+        writeln!(f, "[Result \"{}\"]", self.outcome)?;
+        if let Some(note) = &self.note {
+            writeln!(f, "[Note \"{}\"]", note)?;
+        }
+        // The same `RULES` string the protocol sends, handicap included.
+        writeln!(f, "[Rules \"{}\"]", format_rules(&self.rules))?;
+        if let Some(handicap) = self.rules.handicap {
+            for (x, y) in handicap.points() {
+                writeln!(f, "handicap {},{} {}", x, y, u8::from(handicap.color()))?;
+            }
+        }
+        if let Some(position) = &self.setup {
+            writeln!(f, "[Setup \"{}\"]", format_setup(position))?;
+        }
+
+        for (i, mv) in self.moves.iter().enumerate() {
+            match mv {
+                Move::Place { x, y, color } => {
+                    writeln!(f, "{}. place {},{} {}", i + 1, x, y, u8::from(*color))?
+                }
+                Move::PlaceEntangled {
+                    entanglement,
+                    colors: (first, second),
+                } => writeln!(
+                    f,
+                    "{}. {} {} {}",
+                    i + 1,
+                    format_action(Action::PlaceEntangled(*entanglement)).to_lowercase(),
+                    u8::from(*first),
+                    u8::from(*second)
+                )?,
+                Move::PlaceSuperposed(superposition) => writeln!(
+                    f,
+                    "{}. {} {}",
+                    i + 1,
+                    format_action(Action::PlaceSuperposed(superposition.cells)).to_lowercase(),
+                    u8::from(superposition.color)
+                )?,
+                Move::ObserveRegion { region, board } => writeln!(
+                    f,
+                    "{}. observe {} {}",
+                    i + 1,
+                    format_region(*region),
+                    format_observation(board)
+                )?,
+                Move::Flip { x, y } => writeln!(f, "{}. flip {},{}", i + 1, x, y)?,
+                Move::Erase { x, y, color } => {
+                    writeln!(f, "{}. erase {},{} {}", i + 1, x, y, u8::from(*color))?
+                }
+                Move::PlaceInfluencer { x, y, color } => {
+                    writeln!(f, "{}. influence {},{} {}", i + 1, x, y, u8::from(*color))?
+                }
+                Move::Observe(board) => {
+                    writeln!(f, "{}. observe {}", i + 1, format_observation(board))?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::parse_rules;
+
+    use super::*;
+
+    #[test]
+    fn the_rules_header_reads_back_as_the_same_rules() {
+        let rules =
+            parse_rules("observation-limit=3 handicap=white:4:strong superposition=on").unwrap();
+        let mut game = Game::with_rules(rules);
+        game.place_stone_prohibitly(7, 7).unwrap();
+
+        let record = GameRecord::new("a".into(), "b".into(), 0, &game, Outcome::Draw);
+        let text = record.to_string();
+        let header = text
+            .lines()
+            .find_map(|line| line.strip_prefix("[Rules \"")?.strip_suffix("\"]"))
+            .unwrap();
+
+        assert_eq!(parse_rules(header).unwrap(), rules);
+        assert_eq!(record.first_player(), PlayerKind::Black);
+        assert_eq!(
+            text.lines()
+                .filter(|line| line.starts_with("handicap "))
+                .count(),
+            4
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::ops::Add;
+
+use rayon::prelude::*;
+
+use crate::{
+    ai::{Agent, AgentError},
+    game::{winners_on, Move, ObservedBoard, Rules},
+    grid::Geometry,
+    player::PlayerKind,
+    record::{GameRecord, Outcome},
+    tournament::{check_agent, create_agent, play_game},
+};
+
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    pub rules: Rules,
+    pub black: String,
+    pub white: String,
+    pub games: usize,
+    pub seed: u64,
+    pub max_plies: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Statistics {
+    pub games: usize,
+    pub black_wins: usize,
+    pub white_wins: usize,
+    pub first_player_wins: usize,
+    pub draws: usize,
+    pub plies: usize,
+    pub observations: usize,
+    pub tie_breaks: usize,
+    pub decided_by_tie_break: usize,
+}
+
+impl Statistics {
+    pub fn from_record(record: &GameRecord, geometry: Geometry) -> Statistics {
+        let mut statistics = Statistics {
+            games: 1,
+            plies: record.moves.len(),
+            ..Statistics::default()
+        };
+
+        match record.outcome {
+            Outcome::Win(PlayerKind::Black) => statistics.black_wins = 1,
+            Outcome::Win(PlayerKind::White) => statistics.white_wins = 1,
+            Outcome::Draw => statistics.draws = 1,
+        }
+        if record.outcome == Outcome::Win(record.first_player()) {
+            statistics.first_player_wins = 1;
+        }
+
+        for mv in &record.moves {
+            if let Some(board) = observed_board(mv) {
+                statistics.observations += 1;
+                if winners_on(board, geometry).len() == 2 {
+                    statistics.tie_breaks += 1;
+                }
+            }
+        }
+
+        if let (Some(board), Outcome::Win(_)) =
+            (record.moves.last().and_then(observed_board), record.outcome)
+        {
+            if winners_on(board, geometry).len() == 2 {
+                statistics.decided_by_tie_break = 1;
+            }
+        }
+
+        statistics
+    }
+
+    pub fn first_player_win_rate(&self) -> f64 {
+        ratio(self.first_player_wins, self.games)
+    }
+
+    pub fn second_player_win_rate(&self) -> f64 {
+        ratio(
+            self.black_wins + self.white_wins - self.first_player_wins,
+            self.games,
+        )
+    }
+
+    pub fn draw_rate(&self) -> f64 {
+        ratio(self.draws, self.games)
+    }
+
+    pub fn average_length(&self) -> f64 {
+        ratio(self.plies, self.games)
+    }
+
+    pub fn observation_frequency(&self) -> f64 {
+        ratio(self.observations, self.plies)
+    }
+
+    pub fn tie_break_frequency(&self) -> f64 {
+        ratio(self.decided_by_tie_break, self.games)
+    }
+}
+
+fn observed_board(mv: &Move) -> Option<&ObservedBoard> {
+    match mv {
+        Move::Observe(board) | Move::ObserveRegion { board, .. } => Some(board),
+        _ => None,
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+impl Add for Statistics {
+    type Output = Statistics;
+
+    fn add(self, other: Statistics) -> Statistics {
+        Statistics {
+            games: self.games + other.games,
+            black_wins: self.black_wins + other.black_wins,
+            white_wins: self.white_wins + other.white_wins,
+            first_player_wins: self.first_player_wins + other.first_player_wins,
+            draws: self.draws + other.draws,
+            plies: self.plies + other.plies,
+            observations: self.observations + other.observations,
+            tie_breaks: self.tie_breaks + other.tie_breaks,
+            decided_by_tie_break: self.decided_by_tie_break + other.decided_by_tie_break,
+        }
+    }
+}
+
+type Agents = (Box<dyn Agent + Send>, Box<dyn Agent + Send>);
+
+pub fn simulate(config: &SimulationConfig) -> Result<Statistics, AgentError> {
+    for spec in [&config.black, &config.white] {
+        check_agent(spec)?;
+    }
+
+    (0..config.games as u64)
+        .into_par_iter()
+        .map_init(
+            || -> Result<Agents, AgentError> {
+                Ok((
+                    create_agent(&config.black, 0)?,
+                    create_agent(&config.white, 0)?,
+                ))
+            },
+            |agents, i| {
+                let (black, white) = agents.as_mut().map_err(|err| err.clone())?;
+
+                // Each worker keeps its agents, reseeded for every game so
+                // that results do not depend on which worker plays which game.
+                let seed = config
+                    .seed
+                    .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                    .wrapping_add(i);
+                black.reseed(seed.rotate_left(21));
+                white.reseed(seed.rotate_left(42));
+
+                let record = play_game(
+                    black.as_mut(),
+                    white.as_mut(),
+                    config.rules,
+                    seed,
+                    config.max_plies,
+                );
+                Ok(Statistics::from_record(&record, config.rules.geometry()))
+            },
+        )
+        .try_reduce(Statistics::default, |a, b| Ok(a + b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_do_not_depend_on_the_number_of_threads() {
+        let config = SimulationConfig {
+            rules: Rules::default(),
+            black: "greedy".to_string(),
+            white: "random".to_string(),
+            games: 16,
+            seed: 7,
+            max_plies: 400,
+        };
+        let run = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| simulate(&config))
+        };
+
+        assert_eq!(run(1).unwrap(), run(4).unwrap());
+    }
+
+    #[test]
+    fn unknown_agents_are_reported_instead_of_panicking() {
+        let config = SimulationConfig {
+            rules: Rules::default(),
+            black: "random".to_string(),
+            white: "perfect".to_string(),
+            games: 4,
+            seed: 0,
+            max_plies: 10,
+        };
+
+        assert!(simulate(&config).is_err());
+    }
+}
@@ -0,0 +1,470 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::{
+    bitboard::{Bitboard, StoneBoard},
+    game::{Decoherence, GameError, BOARD_SIZE},
+    player::Color,
+    zobrist,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StoneModelKind {
+    #[default]
+    Classical,
+    Amplitude,
+}
+
+impl StoneModelKind {
+    pub fn create(self) -> Box<dyn StoneModel> {
+        match self {
+            StoneModelKind::Classical => Box::new(ClassicalModel::new()),
+            StoneModelKind::Amplitude => Box::new(AmplitudeModel::new()),
+        }
+    }
+}
+
+// How the stones on the board turn into black and white at observation.
+// `Game` keeps the stone colors on a `StoneBoard` and lets the model hold the
+// per-stone state, starting from the probability of the placed color.
+pub trait StoneModel: Send + Sync {
+    fn kind(&self) -> StoneModelKind;
+
+    fn place(&mut self, x: usize, y: usize, color: Color);
+
+    fn remove(&mut self, x: usize, y: usize);
+
+    fn black_probability(&self, x: usize, y: usize, color: Color) -> f64;
+
+    fn sample(&self, stones: &StoneBoard, draw: &mut dyn FnMut() -> f64) -> Bitboard;
+
+    fn decohere(&mut self, stones: Bitboard, decoherence: Decoherence);
+
+    // Superposed stones have no cell until they settle, so the model keeps
+    // their states apart, in the order they were placed.
+    fn superpose(&mut self, color: Color);
+
+    fn superposed_black_probability(&self, index: usize) -> f64;
+
+    // Drifts the first `count` superposed stones. Certain ones stay certain.
+    fn decohere_superposed(&mut self, count: usize, decoherence: Decoherence);
+
+    // Moves every superposed state onto the cell its stone settled on.
+    fn settle(&mut self, cells: &[(usize, usize)]);
+
+    fn flip(&mut self, _x: usize, _y: usize) -> Result<(), GameError> {
+        Err(GameError::GatesNotSupported)
+    }
+
+    fn collapse(&mut self, _stones: Bitboard, _black: Bitboard) {}
+
+    fn key(&self, _x: usize, _y: usize) -> u64 {
+        0
+    }
+
+    fn superposed_key(&self, _index: usize) -> u64 {
+        0
+    }
+
+    fn clone_box(&self) -> Box<dyn StoneModel>;
+}
+
+impl Clone for Box<dyn StoneModel> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// A stone starts at the probability of its color, which decoherence then
+// drifts and a persistent collapse fixes at 100% or 0%.
+#[derive(Clone, Debug)]
+pub struct ClassicalModel {
+    cells: Vec<Option<u8>>,
+    superposed: Vec<u8>,
+}
+
+impl ClassicalModel {
+    pub fn new() -> ClassicalModel {
+        ClassicalModel {
+            cells: vec![None; BOARD_SIZE * BOARD_SIZE],
+            superposed: Vec::new(),
+        }
+    }
+
+    pub fn probability(&self, x: usize, y: usize) -> Option<u8> {
+        self.cells[y * BOARD_SIZE + x]
+    }
+}
+
+impl Default for ClassicalModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StoneModel for ClassicalModel {
+    fn kind(&self) -> StoneModelKind {
+        StoneModelKind::Classical
+    }
+
+    fn place(&mut self, x: usize, y: usize, color: Color) {
+        self.cells[y * BOARD_SIZE + x] = Some(color.into());
+    }
+
+    fn remove(&mut self, x: usize, y: usize) {
+        self.cells[y * BOARD_SIZE + x] = None;
+    }
+
+    fn black_probability(&self, x: usize, y: usize, color: Color) -> f64 {
+        self.probability(x, y).unwrap_or(color.into()) as f64 / 100.0
+    }
+
+    fn sample(&self, stones: &StoneBoard, draw: &mut dyn FnMut() -> f64) -> Bitboard {
+        let mut black = Bitboard::EMPTY;
+
+        for (color, class) in stones.classes() {
+            for (x, y) in class.iter() {
+                let p = self.probability(x, y).unwrap_or(color.into());
+                let random_number: usize = (draw() * 100.0).floor() as usize;
+                if random_number < p.into() {
+                    black.set(x, y);
+                }
+            }
+        }
+
+        black
+    }
+
+    fn decohere(&mut self, stones: Bitboard, decoherence: Decoherence) {
+        for (x, y) in stones.iter() {
+            let cell = &mut self.cells[y * BOARD_SIZE + x];
+            *cell = cell.map(|p| decoherence.drift(p));
+        }
+    }
+
+    fn superpose(&mut self, color: Color) {
+        self.superposed.push(color.into());
+    }
+
+    fn superposed_black_probability(&self, index: usize) -> f64 {
+        self.superposed[index] as f64 / 100.0
+    }
+
+    fn decohere_superposed(&mut self, count: usize, decoherence: Decoherence) {
+        for p in self.superposed.iter_mut().take(count) {
+            if *p > 0 && *p < 100 {
+                *p = decoherence.drift(*p);
+            }
+        }
+    }
+
+    fn settle(&mut self, cells: &[(usize, usize)]) {
+        for (p, &(x, y)) in std::mem::take(&mut self.superposed).into_iter().zip(cells) {
+            self.cells[y * BOARD_SIZE + x] = Some(p);
+        }
+    }
+
+    fn collapse(&mut self, stones: Bitboard, black: Bitboard) {
+        for (x, y) in stones.iter() {
+            self.cells[y * BOARD_SIZE + x] = Some(if black.get(x, y) { 100 } else { 0 });
+        }
+    }
+
+    fn key(&self, x: usize, y: usize) -> u64 {
+        match self.probability(x, y) {
+            Some(p) => zobrist::probability_key(x, y, p),
+            None => 0,
+        }
+    }
+
+    fn superposed_key(&self, index: usize) -> u64 {
+        zobrist::superposed_probability_key(index, self.superposed[index])
+    }
+
+    fn clone_box(&self) -> Box<dyn StoneModel> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+    pub const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn norm_sqr(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, scale: f64) -> Complex {
+        Complex::new(self.re * scale, self.im * scale)
+    }
+}
+
+// A stone as a two-level state a|black> + b|white>.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Qubit {
+    pub black: Complex,
+    pub white: Complex,
+}
+
+impl Qubit {
+    pub const BLACK: Qubit = Qubit {
+        black: Complex::ONE,
+        white: Complex::ZERO,
+    };
+    pub const WHITE: Qubit = Qubit {
+        black: Complex::ZERO,
+        white: Complex::ONE,
+    };
+
+    pub fn from_probability(p: f64) -> Qubit {
+        Qubit {
+            black: Complex::new(p.sqrt(), 0.0),
+            white: Complex::new((1.0 - p).sqrt(), 0.0),
+        }
+    }
+
+    // The same phases with the magnitudes rescaled to probability `p`. An
+    // amplitude of zero has no phase to keep and comes back real.
+    pub fn with_black_probability(&self, p: f64) -> Qubit {
+        let rescale = |amplitude: Complex, p: f64| {
+            let norm = amplitude.norm_sqr().sqrt();
+            if norm > 0.0 {
+                amplitude * (p.sqrt() / norm)
+            } else {
+                Complex::new(p.sqrt(), 0.0)
+            }
+        };
+
+        Qubit {
+            black: rescale(self.black, p),
+            white: rescale(self.white, 1.0 - p),
+        }
+    }
+
+    // Born rule.
+    pub fn black_probability(&self) -> f64 {
+        self.black.norm_sqr() / (self.black.norm_sqr() + self.white.norm_sqr())
+    }
+
+    // Hadamard gate: flipping twice interferes back to the original stone,
+    // and a certain stone flips to an even one.
+    pub fn flip(&self) -> Qubit {
+        let scale = std::f64::consts::FRAC_1_SQRT_2;
+        Qubit {
+            black: (self.black + self.white) * scale,
+            white: (self.black - self.white) * scale,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AmplitudeModel {
+    cells: Vec<Option<Qubit>>,
+    superposed: Vec<Qubit>,
+}
+
+impl AmplitudeModel {
+    pub fn new() -> AmplitudeModel {
+        AmplitudeModel {
+            cells: vec![None; BOARD_SIZE * BOARD_SIZE],
+            superposed: Vec::new(),
+        }
+    }
+
+    pub fn qubit(&self, x: usize, y: usize) -> Option<Qubit> {
+        self.cells[y * BOARD_SIZE + x]
+    }
+}
+
+impl Default for AmplitudeModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StoneModel for AmplitudeModel {
+    fn kind(&self) -> StoneModelKind {
+        StoneModelKind::Amplitude
+    }
+
+    fn place(&mut self, x: usize, y: usize, color: Color) {
+        let p = u8::from(color) as f64 / 100.0;
+        self.cells[y * BOARD_SIZE + x] = Some(Qubit::from_probability(p));
+    }
+
+    fn remove(&mut self, x: usize, y: usize) {
+        self.cells[y * BOARD_SIZE + x] = None;
+    }
+
+    fn black_probability(&self, x: usize, y: usize, color: Color) -> f64 {
+        match self.qubit(x, y) {
+            Some(qubit) => qubit.black_probability(),
+            None => u8::from(color) as f64 / 100.0,
+        }
+    }
+
+    fn sample(&self, stones: &StoneBoard, draw: &mut dyn FnMut() -> f64) -> Bitboard {
+        let mut black = Bitboard::EMPTY;
+
+        for (x, y) in stones.occupied().iter() {
+            if let Some(qubit) = self.qubit(x, y) {
+                if draw() < qubit.black_probability() {
+                    black.set(x, y);
+                }
+            }
+        }
+
+        black
+    }
+
+    // Decoherence drifts the probability and keeps the phases, so a stone
+    // that has drifted still interferes when flipped.
+    fn decohere(&mut self, stones: Bitboard, decoherence: Decoherence) {
+        for (x, y) in stones.iter() {
+            let cell = &mut self.cells[y * BOARD_SIZE + x];
+            if let Some(qubit) = cell {
+                let p = (qubit.black_probability() * 100.0).round() as u8;
+                let drifted = decoherence.drift(p);
+                if drifted != p {
+                    *qubit = qubit.with_black_probability(drifted as f64 / 100.0);
+                }
+            }
+        }
+    }
+
+    fn superpose(&mut self, color: Color) {
+        let p = u8::from(color) as f64 / 100.0;
+        self.superposed.push(Qubit::from_probability(p));
+    }
+
+    fn superposed_black_probability(&self, index: usize) -> f64 {
+        self.superposed[index].black_probability()
+    }
+
+    fn decohere_superposed(&mut self, count: usize, decoherence: Decoherence) {
+        for qubit in self.superposed.iter_mut().take(count) {
+            let p = (qubit.black_probability() * 100.0).round() as u8;
+            if p > 0 && p < 100 {
+                let drifted = decoherence.drift(p);
+                if drifted != p {
+                    *qubit = qubit.with_black_probability(drifted as f64 / 100.0);
+                }
+            }
+        }
+    }
+
+    fn settle(&mut self, cells: &[(usize, usize)]) {
+        for (qubit, &(x, y)) in std::mem::take(&mut self.superposed).into_iter().zip(cells) {
+            self.cells[y * BOARD_SIZE + x] = Some(qubit);
+        }
+    }
+
+    fn flip(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        let cell = &mut self.cells[y * BOARD_SIZE + x];
+        match cell {
+            Some(qubit) => {
+                *qubit = qubit.flip();
+                Ok(())
+            }
+            None => Err(GameError::InvalidPosition(None)),
+        }
+    }
+
+    fn collapse(&mut self, stones: Bitboard, black: Bitboard) {
+        for (x, y) in stones.iter() {
+            let cell = &mut self.cells[y * BOARD_SIZE + x];
+            if cell.is_some() {
+                *cell = Some(if black.get(x, y) {
+                    Qubit::BLACK
+                } else {
+                    Qubit::WHITE
+                });
+            }
+        }
+    }
+
+    fn key(&self, x: usize, y: usize) -> u64 {
+        match self.qubit(x, y) {
+            Some(qubit) => zobrist::amplitude_key(
+                x,
+                y,
+                [
+                    qubit.black.re,
+                    qubit.black.im,
+                    qubit.white.re,
+                    qubit.white.im,
+                ],
+            ),
+            None => 0,
+        }
+    }
+
+    fn superposed_key(&self, index: usize) -> u64 {
+        let qubit = self.superposed[index];
+        zobrist::superposed_amplitude_key(
+            index,
+            [
+                qubit.black.re,
+                qubit.black.im,
+                qubit.white.re,
+                qubit.white.im,
+            ],
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn StoneModel> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rescaling_a_qubit_keeps_its_phases() {
+        // A strong white stone flipped once: 80% black, with a negative white
+        // amplitude that a second flip interferes with.
+        let flipped = Qubit::from_probability(0.1).flip();
+        assert!((flipped.black_probability() - 0.8).abs() < 1e-9);
+
+        let kept = flipped.with_black_probability(flipped.black_probability());
+        assert!((kept.flip().black_probability() - 0.1).abs() < 1e-9);
+
+        let drifted = flipped.with_black_probability(0.7);
+        assert!((drifted.black_probability() - 0.7).abs() < 1e-9);
+        assert!(drifted.white.re < 0.0);
+
+        let even = Qubit::BLACK.with_black_probability(0.5);
+        assert!((even.black_probability() - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::player::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Superposition {
+    pub cells: [(usize, usize); 2],
+    pub color: Color,
+}
+
+impl Superposition {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.cells.contains(&(x, y))
+    }
+}
+
+// Superposed stones may share cells, so chains and cycles of stones constrain
+// each other: every stone has to land on a different cell. An arrangement
+// exists exactly when each connected group has no more stones than cells.
+pub fn is_consistent(stones: &[Superposition]) -> bool {
+    arrange(stones, &vec![None; stones.len()]).is_some()
+}
+
+// An arrangement that only uses cells where a stone was actually seen.
+pub fn arrangement_within(
+    stones: &[Superposition],
+    seen: impl Fn((usize, usize)) -> bool,
+) -> Option<Vec<(usize, usize)>> {
+    let candidates = stones
+        .iter()
+        .map(|stone| stone.cells.into_iter().filter(|&cell| seen(cell)).collect())
+        .collect();
+    assign(candidates)
+}
+
+// Each stone in turn, in random order, lands on one of its two cells at
+// random, unless that would leave the remaining stones without an arrangement.
+// A cycle therefore settles into one of its two rotations.
+pub fn collapse<R: Rng + ?Sized>(stones: &[Superposition], rng: &mut R) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..stones.len()).collect();
+    order.shuffle(rng);
+
+    let mut chosen = vec![None; stones.len()];
+    for i in order {
+        let mut cells = stones[i].cells;
+        cells.shuffle(rng);
+
+        chosen[i] = Some(cells[0]);
+        if arrange(stones, &chosen).is_none() {
+            chosen[i] = Some(cells[1]);
+        }
+    }
+
+    arrange(stones, &chosen).expect("superposed stones are kept consistent")
+}
+
+fn arrange(
+    stones: &[Superposition],
+    chosen: &[Option<(usize, usize)>],
+) -> Option<Vec<(usize, usize)>> {
+    let candidates = stones
+        .iter()
+        .zip(chosen)
+        .map(|(stone, chosen)| match chosen {
+            Some(cell) => vec![*cell],
+            None => stone.cells.to_vec(),
+        })
+        .collect();
+    assign(candidates)
+}
+
+fn assign(candidates: Vec<Vec<(usize, usize)>>) -> Option<Vec<(usize, usize)>> {
+    let mut owner = HashMap::new();
+    for i in 0..candidates.len() {
+        if !augment(i, &candidates, &mut owner, &mut Vec::new()) {
+            return None;
+        }
+    }
+
+    let mut cells = vec![(0, 0); candidates.len()];
+    for (cell, i) in owner {
+        cells[i] = cell;
+    }
+    Some(cells)
+}
+
+fn augment(
+    i: usize,
+    candidates: &[Vec<(usize, usize)>],
+    owner: &mut HashMap<(usize, usize), usize>,
+    visited: &mut Vec<(usize, usize)>,
+) -> bool {
+    for &cell in &candidates[i] {
+        if visited.contains(&cell) {
+            continue;
+        }
+        visited.push(cell);
+
+        let free = match owner.get(&cell) {
+            Some(&other) => augment(other, candidates, owner, visited),
+            None => true,
+        };
+        if free {
+            owner.insert(cell, i);
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::player::PColor;
+
+    fn stone(a: (usize, usize), b: (usize, usize)) -> Superposition {
+        Superposition {
+            cells: [a, b],
+            color: Color::Black(PColor::Strong),
+        }
+    }
+
+    #[test]
+    fn consistent_while_no_group_has_more_stones_than_cells() {
+        let chain = [stone((0, 0), (1, 0)), stone((1, 0), (2, 0))];
+        let cycle = [
+            stone((0, 0), (1, 0)),
+            stone((1, 0), (2, 0)),
+            stone((2, 0), (0, 0)),
+        ];
+        assert!(is_consistent(&chain));
+        assert!(is_consistent(&cycle));
+
+        let crowded = [
+            stone((0, 0), (1, 0)),
+            stone((1, 0), (0, 0)),
+            stone((0, 0), (1, 0)),
+        ];
+        let overfull = [cycle.as_slice(), &[stone((0, 0), (2, 0))]].concat();
+        assert!(!is_consistent(&crowded));
+        assert!(!is_consistent(&overfull));
+    }
+
+    #[test]
+    fn a_cycle_collapses_into_one_of_its_rotations() {
+        let cycle = [
+            stone((0, 0), (1, 0)),
+            stone((1, 0), (2, 0)),
+            stone((2, 0), (0, 0)),
+        ];
+        let forward = vec![(0, 0), (1, 0), (2, 0)];
+        let backward = vec![(1, 0), (2, 0), (0, 0)];
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut seen = HashMap::new();
+        for _ in 0..200 {
+            let cells = collapse(&cycle, &mut rng);
+            assert!(cells == forward || cells == backward, "{:?}", cells);
+            *seen.entry(cells).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn arrangement_only_uses_the_cells_that_were_seen() {
+        let chain = [stone((0, 0), (1, 0)), stone((1, 0), (2, 0))];
+
+        let seen = |cell: (usize, usize)| cell != (2, 0);
+        assert_eq!(arrangement_within(&chain, seen), Some(vec![(0, 0), (1, 0)]));
+
+        // Both stones cannot land on the one cell left.
+        let seen = |cell: (usize, usize)| cell == (1, 0);
+        assert_eq!(arrangement_within(&chain, seen), None);
+    }
+}
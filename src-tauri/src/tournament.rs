@@ -0,0 +1,278 @@
+use std::{fs, io, path::PathBuf};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    ai::{Agent, AgentError, GreedyAgent, RandomAgent},
+    game::{Game, Rules},
+    player::PlayerKind,
+    protocol::ExternalEngine,
+    rating::MatchResult,
+    record::{GameRecord, Outcome},
+};
+
+// Whether `spec` names an agent, without starting the engine it may name.
+pub fn check_agent(spec: &str) -> Result<(), AgentError> {
+    match spec.split_once(':') {
+        Some(("engine", command)) if !command.trim().is_empty() => Ok(()),
+        _ if spec == "random" || spec == "greedy" => Ok(()),
+        _ => Err(AgentError::Engine(format!("unknown agent: {}", spec))),
+    }
+}
+
+pub fn create_agent(spec: &str, seed: u64) -> Result<Box<dyn Agent + Send>, AgentError> {
+    match spec.split_once(':') {
+        Some(("engine", command)) => ExternalEngine::spawn(command)
+            .map(|engine| Box::new(engine) as Box<dyn Agent + Send>)
+            .map_err(|err| AgentError::Engine(err.to_string())),
+        _ => match spec {
+            "random" => Ok(Box::new(RandomAgent::new(seed))),
+            "greedy" => Ok(Box::new(GreedyAgent::new(seed))),
+            _ => Err(AgentError::Engine(format!("unknown agent: {}", spec))),
+        },
+    }
+}
+
+pub fn play_game<'a>(
+    black: &mut (dyn Agent + 'a),
+    white: &mut (dyn Agent + 'a),
+    rules: Rules,
+    seed: u64,
+    max_plies: usize,
+) -> GameRecord {
+    let mut game = Game::with_rules(rules);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let names = (black.name(), white.name());
+    let record = |game: &Game, outcome: Outcome, note: Option<String>| GameRecord {
+        note,
+        ..GameRecord::new(names.0.clone(), names.1.clone(), seed, game, outcome)
+    };
+
+    for (agent, kind) in [
+        (&mut *black, PlayerKind::Black),
+        (&mut *white, PlayerKind::White),
+    ] {
+        if let Err(err) = agent.new_game() {
+            return record(&game, Outcome::Win(kind.opponent()), Some(err.to_string()));
+        }
+    }
+
+    for _ in 0..max_plies {
+        if let Some(winner) = game.winner() {
+            return record(&game, Outcome::Win(winner), None);
+        }
+
+        let mover = game.turn();
+        let agent = match mover {
+            PlayerKind::Black => &mut *black,
+            PlayerKind::White => &mut *white,
+        };
+
+        let result = agent
+            .select_action(&game)
+            .map_err(|err| err.to_string())
+            .and_then(|action| {
+                game.apply_action_with(action, &mut rng)
+                    .map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = result {
+            let note = format!("{} forfeits: {}", mover, err);
+            return record(&game, Outcome::Win(mover.opponent()), Some(note));
+        }
+    }
+
+    match game.winner() {
+        Some(winner) => record(&game, Outcome::Win(winner), None),
+        None => record(&game, Outcome::Draw, Some("move limit reached".to_string())),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    RoundRobin,
+    Swiss { rounds: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentConfig {
+    pub format: Format,
+    pub rules: Rules,
+    pub games_per_pairing: usize,
+    pub seed: u64,
+    pub max_plies: usize,
+    pub record_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Standing {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Standing {
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    pub standings: Vec<Standing>,
+    pub results: Vec<MatchResult>,
+}
+
+pub struct Tournament<'a> {
+    agents: &'a mut [Box<dyn Agent + Send>],
+    config: TournamentConfig,
+    standings: Vec<Standing>,
+    results: Vec<MatchResult>,
+}
+
+impl<'a> Tournament<'a> {
+    pub fn new(agents: &'a mut [Box<dyn Agent + Send>], config: TournamentConfig) -> Self {
+        let standings = vec![Standing::default(); agents.len()];
+        Tournament {
+            agents,
+            config,
+            standings,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn run(mut self) -> io::Result<TournamentResult> {
+        if let Some(dir) = &self.config.record_dir {
+            fs::create_dir_all(dir)?;
+        }
+
+        match self.config.format {
+            Format::RoundRobin => {
+                for a in 0..self.agents.len() {
+                    for b in a + 1..self.agents.len() {
+                        self.play_pairing(a, b)?;
+                    }
+                }
+            }
+            Format::Swiss { rounds } => {
+                for _ in 0..rounds {
+                    for (a, b) in self.swiss_pairings() {
+                        self.play_pairing(a, b)?;
+                    }
+                }
+            }
+        }
+
+        Ok(TournamentResult {
+            names: self.agents.iter().map(|agent| agent.name()).collect(),
+            standings: self.standings,
+            results: self.results,
+        })
+    }
+
+    fn swiss_pairings(&self) -> Vec<(usize, usize)> {
+        let mut order: Vec<usize> = (0..self.agents.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.standings[b]
+                .score()
+                .total_cmp(&self.standings[a].score())
+        });
+
+        let played = |a: usize, b: usize| {
+            self.results.iter().any(|result| {
+                (result.black == a && result.white == b) || (result.black == b && result.white == a)
+            })
+        };
+
+        let mut pairings = Vec::new();
+        while order.len() >= 2 {
+            let a = order.remove(0);
+            let index = order.iter().position(|&b| !played(a, b)).unwrap_or(0);
+            pairings.push((a, order.remove(index)));
+        }
+
+        pairings
+    }
+
+    fn play_pairing(&mut self, a: usize, b: usize) -> io::Result<()> {
+        let (a, b) = if self.games_as_black(a) <= self.games_as_black(b) {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        for i in 0..self.config.games_per_pairing {
+            let (black, white) = if i % 2 == 0 { (a, b) } else { (b, a) };
+            let seed = self
+                .config
+                .seed
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                .wrapping_add(self.results.len() as u64);
+
+            let (first, second) = self.agents.split_at_mut(black.max(white));
+            let (black_agent, white_agent) = if black < white {
+                (&mut first[black], &mut second[0])
+            } else {
+                (&mut second[0], &mut first[white])
+            };
+
+            let record = play_game(
+                black_agent.as_mut(),
+                white_agent.as_mut(),
+                self.config.rules,
+                seed,
+                self.config.max_plies,
+            );
+
+            if let Some(dir) = &self.config.record_dir {
+                let name = format!(
+                    "{:05}-{}-vs-{}.txt",
+                    self.results.len() + 1,
+                    sanitize(&record.black),
+                    sanitize(&record.white)
+                );
+                record.save(dir.join(name))?;
+            }
+
+            self.add_result(MatchResult {
+                black,
+                white,
+                outcome: record.outcome,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn games_as_black(&self, player: usize) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.black == player)
+            .count()
+    }
+
+    fn add_result(&mut self, result: MatchResult) {
+        match result.outcome {
+            Outcome::Win(PlayerKind::Black) => {
+                self.standings[result.black].wins += 1;
+                self.standings[result.white].losses += 1;
+            }
+            Outcome::Win(PlayerKind::White) => {
+                self.standings[result.white].wins += 1;
+                self.standings[result.black].losses += 1;
+            }
+            Outcome::Draw => {
+                self.standings[result.black].draws += 1;
+                self.standings[result.white].draws += 1;
+            }
+        }
+
+        self.results.push(result);
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,363 @@
+use std::sync::OnceLock;
+
+use crate::{
+    bitboard::{ObservedBits, STONE_CLASSES},
+    game::{Correlation, Entanglement, Game, Region, BOARD_SIZE},
+    player::{Color, PColor, Player, PlayerKind},
+    superposition::Superposition,
+};
+
+const MAX_BUDGET: usize = u8::MAX as usize;
+
+struct Keys {
+    cells: Vec<[u64; STONE_CLASSES.len()]>,
+    white_to_move: u64,
+    next_stone: [[u64; 3]; 2],
+    budget: [Vec<u64>; 2],
+    entangled: Vec<[u64; 2]>,
+    superposed: Vec<[u64; STONE_CLASSES.len()]>,
+    observed: Vec<[u64; 2]>,
+    influencer: Vec<u64>,
+    certain_stones: [Vec<u64>; 2],
+    second_member: [u64; 2],
+}
+
+// SplitMix64 with a fixed seed, so hashes are stable across runs and can be
+// written to disk alongside positions.
+fn keys() -> &'static Keys {
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+
+    KEYS.get_or_init(|| {
+        let mut state: u64 = 0x5155_414E_5455_4D21;
+        let mut next = || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            mix(state)
+        };
+
+        Keys {
+            cells: (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|_| STONE_CLASSES.map(|_| next()))
+                .collect(),
+            white_to_move: next(),
+            next_stone: [[next(), next(), next()], [next(), next(), next()]],
+            budget: [
+                (0..=MAX_BUDGET).map(|_| next()).collect(),
+                (0..=MAX_BUDGET).map(|_| next()).collect(),
+            ],
+            entangled: (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|_| [next(), next()])
+                .collect(),
+            superposed: (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|_| STONE_CLASSES.map(|_| next()))
+                .collect(),
+            observed: (0..BOARD_SIZE * BOARD_SIZE)
+                .map(|_| [next(), next()])
+                .collect(),
+            influencer: (0..BOARD_SIZE * BOARD_SIZE).map(|_| next()).collect(),
+            certain_stones: [
+                (0..=MAX_BUDGET).map(|_| next()).collect(),
+                (0..=MAX_BUDGET).map(|_| next()).collect(),
+            ],
+            second_member: [next(), next()],
+        }
+    })
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn player_index(kind: PlayerKind) -> usize {
+    match kind {
+        PlayerKind::Black => 0,
+        PlayerKind::White => 1,
+    }
+}
+
+fn class_index(color: Color) -> usize {
+    STONE_CLASSES
+        .iter()
+        .position(|&class| class == color)
+        .expect("every color has a stone class")
+}
+
+pub fn stone_key(x: usize, y: usize, color: Color) -> u64 {
+    keys().cells[y * BOARD_SIZE + x][class_index(color)]
+}
+
+pub fn superposition_key(superposition: &Superposition) -> u64 {
+    let class = class_index(superposition.color);
+    let [a, b] = superposition.cells.map(|(x, y)| y * BOARD_SIZE + x);
+
+    keys().superposed[a.min(b)][class] ^ keys().superposed[a.max(b)][class].rotate_left(1)
+}
+
+pub fn entanglement_key(entanglement: &Entanglement) -> u64 {
+    let correlation = match entanglement.correlation {
+        Correlation::Correlated => 0,
+        Correlation::Anticorrelated => 1,
+    };
+    let (x1, y1) = entanglement.first;
+    let (x2, y2) = entanglement.second;
+
+    keys().entangled[y1 * BOARD_SIZE + x1][correlation]
+        ^ keys().entangled[y2 * BOARD_SIZE + x2][correlation].rotate_left(1)
+}
+
+// Amplitudes are rounded so that a stone flipped twice hashes like the stone
+// it started as, despite floating-point error.
+pub fn amplitude_key(x: usize, y: usize, amplitudes: [f64; 4]) -> u64 {
+    fold_amplitudes(keys().cells[y * BOARD_SIZE + x][0], amplitudes)
+}
+
+// A superposed stone has no cell yet, so its state is keyed on the order it
+// was placed in.
+pub fn superposed_amplitude_key(index: usize, amplitudes: [f64; 4]) -> u64 {
+    fold_amplitudes(keys().superposed[index][0], amplitudes)
+}
+
+fn fold_amplitudes(seed: u64, amplitudes: [f64; 4]) -> u64 {
+    amplitudes.iter().fold(seed, |hash, amplitude| {
+        mix(hash ^ (amplitude * 1e6).round() as i64 as u64)
+    })
+}
+
+pub fn influencer_key(x: usize, y: usize) -> u64 {
+    keys().influencer[y * BOARD_SIZE + x]
+}
+
+pub fn probability_key(x: usize, y: usize, p: u8) -> u64 {
+    mix(keys().cells[y * BOARD_SIZE + x][0] ^ p as u64)
+}
+
+pub fn superposed_probability_key(index: usize, p: u8) -> u64 {
+    mix(keys().superposed[index][0] ^ p as u64)
+}
+
+// Only part of the hash under regional observation, where what was seen last
+// time still counts towards later wins.
+pub fn observed_key(observed: &ObservedBits) -> u64 {
+    observed.stones.iter().fold(0, |hash, (x, y)| {
+        let kind = if observed.black.get(x, y) {
+            PlayerKind::Black
+        } else {
+            PlayerKind::White
+        };
+        hash ^ observed_cell_key(x, y, kind)
+    })
+}
+
+pub fn observed_cell_key(x: usize, y: usize, kind: PlayerKind) -> u64 {
+    keys().observed[y * BOARD_SIZE + x][player_index(kind)]
+}
+
+// Sets apart what is known about observing different regions of one
+// position.
+pub fn region_key(region: Region) -> u64 {
+    let (kind, x, y) = match region {
+        Region::Window(x, y) => (1, x, y),
+        Region::Row(y) => (2, 0, y),
+        Region::Column(x) => (3, x, 0),
+        Region::Diagonal(x, y) => (4, x, y),
+        Region::AntiDiagonal(x, y) => (5, x, y),
+    };
+
+    mix(keys().cells[y * BOARD_SIZE + x][0] ^ kind)
+}
+
+pub fn side_key(turn: PlayerKind) -> u64 {
+    match turn {
+        PlayerKind::Black => 0,
+        PlayerKind::White => keys().white_to_move,
+    }
+}
+
+pub fn player_key(player: &Player) -> u64 {
+    let index = player_index(player.kind());
+    let stone = match player.next_stone() {
+        PColor::Strong => 0,
+        PColor::Weak => 1,
+        PColor::Certain => 2,
+    };
+    let budget = match player.observations_left() {
+        Some(left) => keys().budget[index][left as usize],
+        None => 0,
+    };
+
+    let member = match player.member() {
+        0 => 0,
+        _ => keys().second_member[index],
+    };
+
+    keys().next_stone[index][stone]
+        ^ budget
+        ^ keys().certain_stones[index][player.certain_stones_left() as usize]
+        ^ member
+}
+
+// The teammate waiting for their turn in a team game.
+pub fn reserve_key(player: &Player) -> u64 {
+    player_key(player).rotate_left(1)
+}
+
+pub fn hash(game: &Game) -> u64 {
+    let mut hash = side_key(game.turn())
+        ^ player_key(game.player(PlayerKind::Black))
+        ^ player_key(game.player(PlayerKind::White));
+
+    for kind in [PlayerKind::Black, PlayerKind::White] {
+        if let Some(teammate) = game.teammate(kind) {
+            hash ^= reserve_key(teammate);
+        }
+    }
+
+    for (color, class) in game.stones().classes() {
+        for (x, y) in class.iter() {
+            hash ^= stone_key(x, y, color) ^ game.model().key(x, y);
+        }
+    }
+
+    for entanglement in game.entanglements() {
+        hash ^= entanglement_key(entanglement);
+    }
+
+    for (index, superposition) in game.superpositions().iter().enumerate() {
+        hash ^= superposition_key(superposition) ^ game.model().superposed_key(index);
+    }
+
+    for (x, y) in game.influencers().iter() {
+        hash ^= influencer_key(x, y);
+    }
+
+    if game.rules().regional_observation {
+        hash ^= observed_key(game.observed());
+    }
+
+    hash
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry<T> {
+    pub key: u64,
+    pub depth: u8,
+    pub value: T,
+}
+
+pub struct TranspositionTable<T> {
+    entries: Vec<Option<Entry<T>>>,
+    mask: usize,
+    len: usize,
+}
+
+impl<T> TranspositionTable<T> {
+    pub fn new(capacity: usize) -> TranspositionTable<T> {
+        let size = capacity.max(1).next_power_of_two();
+        TranspositionTable {
+            entries: (0..size).map(|_| None).collect(),
+            mask: size - 1,
+            len: 0,
+        }
+    }
+
+    pub fn get(&self, key: u64) -> Option<&Entry<T>> {
+        self.entries[key as usize & self.mask]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    // Deeper results are kept over shallower ones for the same slot, but any
+    // entry for a different position is replaced.
+    pub fn insert(&mut self, key: u64, depth: u8, value: T) {
+        let slot = &mut self.entries[key as usize & self.mask];
+        match slot {
+            Some(entry) if entry.key == key && entry.depth > depth => (),
+            Some(_) => *slot = Some(Entry { key, depth, value }),
+            None => {
+                *slot = Some(Entry { key, depth, value });
+                self.len += 1;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        ai::{Agent, RandomAgent},
+        protocol::parse_rules,
+    };
+
+    #[test]
+    fn incremental_hash_matches_a_full_recomputation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for rules in [
+            "",
+            "observation-limit=3 certain-stones=2 influence=20",
+            "entanglement=on superposition=on collapse=persistent",
+            "superposition=on model=amplitude decoherence=certain:5 collapse=persistent",
+            "model=amplitude decoherence=even:5",
+            "observation=region collapse=persistent erasure=60",
+            "teams=on topology=toroidal",
+            "grid=hex decoherence=certain:5",
+            "gravity=on",
+            "handicap=white:4:strong",
+        ] {
+            let rules = parse_rules(rules).unwrap();
+            for seed in 0..10 {
+                let mut agent = RandomAgent::new(seed);
+                let mut game = Game::with_rules(rules);
+                assert_eq!(game.hash(), hash(&game));
+
+                while let Ok(action) = agent.select_action(&game) {
+                    if game.apply_action_with(action, &mut rng).is_err() {
+                        break;
+                    }
+                    assert_eq!(game.hash(), hash(&game), "{:?} after {:?}", rules, action);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn table_keeps_the_deeper_entry_for_a_position() {
+        let mut table = TranspositionTable::new(3);
+        assert_eq!(table.capacity(), 4);
+        assert!(table.is_empty());
+
+        table.insert(1, 2, "deep");
+        table.insert(1, 1, "shallow");
+        assert_eq!(table.get(1).map(|entry| entry.value), Some("deep"));
+
+        // Another position in the same slot replaces it.
+        table.insert(5, 0, "other");
+        assert!(table.get(1).is_none());
+        assert_eq!(table.get(5).map(|entry| entry.value), Some("other"));
+        assert_eq!(table.len(), 1);
+
+        table.clear();
+        assert!(table.get(5).is_none());
+        assert!(table.is_empty());
+    }
+}